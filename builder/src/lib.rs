@@ -1,8 +1,605 @@
 use proc_macro::TokenStream;
+use proc_macro2::{Span, TokenStream as TokenStream2, TokenTree};
+use quote::{format_ident, quote, ToTokens};
+use syn::punctuated::Punctuated;
+use syn::visit::{self, Visit};
+use syn::{
+    parse_macro_input, Data, DeriveInput, Error, Expr, ExprPath, Fields, GenericArgument, Ident,
+    Lit, LitStr, Macro, Meta, Path, PathArguments, Result, Token, Type,
+};
 
-#[proc_macro_derive(Builder)]
+#[proc_macro_derive(Builder, attributes(builder))]
 pub fn derive(input: TokenStream) -> TokenStream {
-    let _ = input;
+    let input = parse_macro_input!(input as DeriveInput);
 
-    unimplemented!()
+    expand(&input)
+        .unwrap_or_else(Error::into_compile_error)
+        .into()
+}
+
+fn expand(input: &DeriveInput) -> Result<TokenStream2> {
+    let name = &input.ident;
+    let vis = &input.vis;
+    let builder_name = format_ident!("{}Builder", name);
+    let (impl_generics, ty_generics, where_clause) = input.generics.split_for_impl();
+
+    let fields = match &input.data {
+        Data::Struct(data) => match &data.fields {
+            Fields::Named(fields) => &fields.named,
+            _ => {
+                return Err(Error::new(
+                    Span::call_site(),
+                    "Builder can only be derived for structs with named fields",
+                ))
+            }
+        },
+        _ => {
+            return Err(Error::new(
+                Span::call_site(),
+                "Builder can only be derived for structs",
+            ))
+        }
+    };
+
+    let mut errors = Errors::default();
+    let mut fields = fields
+        .iter()
+        .filter_map(|field| errors.ok(Field::new(field)))
+        .collect::<Vec<_>>();
+    errors.finish()?;
+
+    let dependencies = fields
+        .iter()
+        .map(|field| match &field.default {
+            Some(DefaultValue::Expr { expr, .. }) => {
+                let mut refs = FieldRefs {
+                    fields: &fields,
+                    found: Vec::new(),
+                };
+                refs.visit_expr(expr);
+                refs.found
+            }
+            _ => Vec::new(),
+        })
+        .collect::<Vec<_>>();
+    for (field, found) in fields.iter_mut().zip(dependencies) {
+        if let Some(DefaultValue::Expr { dependencies, .. }) = &mut field.default {
+            *dependencies = found;
+        }
+    }
+
+    let order = resolution_order(&fields)?;
+
+    let storage = fields.iter().map(Field::storage);
+    let initializers = fields.iter().map(Field::initializer);
+    let setters = fields.iter().map(Field::setters);
+    let checks = fields.iter().filter_map(Field::check);
+    let lazy_defaults = fields.iter().filter_map(Field::lazy_default);
+    let resolved = order.iter().map(|&i| fields[i].resolve(&fields));
+    let field_names = fields.iter().map(|field| field.ident);
+
+    Ok(quote! {
+        #vis struct #builder_name #impl_generics #where_clause {
+            #(#storage,)*
+        }
+
+        impl #impl_generics #name #ty_generics #where_clause {
+            #vis fn builder() -> #builder_name #ty_generics {
+                #builder_name {
+                    #(#initializers,)*
+                }
+            }
+        }
+
+        impl #impl_generics #builder_name #ty_generics #where_clause {
+            #(#setters)*
+
+            #vis fn build(
+                &mut self,
+            ) -> ::std::result::Result<
+                #name #ty_generics,
+                ::std::boxed::Box<dyn ::std::error::Error>,
+            > {
+                #(#checks)*
+                #(#lazy_defaults)*
+                #(#resolved)*
+                ::std::result::Result::Ok(#name {
+                    #(#field_names,)*
+                })
+            }
+        }
+    })
+}
+
+struct Field<'a> {
+    ident: &'a Ident,
+    ty: &'a Type,
+    kind: Kind<'a>,
+    /// Attributes requested through `#[builder(field_attrs(...))]`, emitted
+    /// on the builder's storage field.
+    field_attrs: Vec<Meta>,
+    default: Option<DefaultValue>,
+}
+
+enum DefaultValue {
+    /// `#[builder(default = "...")]`, evaluated in `build()` with every field
+    /// it mentions in scope by reference.
+    Expr {
+        lit: LitStr,
+        expr: Expr,
+        /// Other fields named by the expression, which must be resolved first.
+        dependencies: Vec<Ident>,
+    },
+    /// `#[builder(default_with = "...")]`, a function taking the builder by
+    /// reference and returning `Result<T, E>`.
+    With(ExprPath),
+}
+
+enum Kind<'a> {
+    /// A plain field, which must be set before calling `build()`.
+    Required,
+    /// An `Option<T>` field, which is left as `None` if never set.
+    Optional(&'a Type),
+    /// A `Vec<T>` field with `#[builder(each = "...")]`, which is filled in one
+    /// element at a time and is empty if never set.
+    Repeated(&'a Type, Ident),
+}
+
+impl<'a> Field<'a> {
+    fn new(field: &'a syn::Field) -> Result<Self> {
+        let ident = field.ident.as_ref().unwrap();
+        let ty = &field.ty;
+
+        let mut each = None;
+        let mut default = None;
+        let mut field_attrs = Vec::new();
+        let mut errors = Errors::default();
+        for attr in &field.attrs {
+            if !attr.path().is_ident("builder") {
+                continue;
+            }
+            let parsed = attr.parse_nested_meta(|meta| {
+                if meta.path.is_ident("each") {
+                    let lit: LitStr = meta.value()?.parse()?;
+                    each = Some(lit.parse::<Ident>()?);
+                } else if meta.path.is_ident("default") {
+                    let lit: LitStr = meta.value()?.parse()?;
+                    let expr = lit.parse()?;
+                    default = Some(DefaultValue::Expr {
+                        lit,
+                        expr,
+                        dependencies: Vec::new(),
+                    });
+                } else if meta.path.is_ident("default_with") {
+                    let lit: LitStr = meta.value()?.parse()?;
+                    default = Some(DefaultValue::With(lit.parse()?));
+                } else if meta.path.is_ident("field_attrs") {
+                    let content;
+                    syn::parenthesized!(content in meta.input);
+                    let attrs = Punctuated::<Meta, Token![,]>::parse_terminated(&content)?;
+                    field_attrs.extend(attrs);
+                } else {
+                    // Keep going so that every bad key is reported at once.
+                    errors.push(unknown_key(&meta.path));
+                    while !meta.input.is_empty() && !meta.input.peek(Token![,]) {
+                        meta.input.parse::<TokenTree>()?;
+                    }
+                }
+                Ok(())
+            });
+            errors.ok(parsed);
+        }
+        errors.finish()?;
+
+        let kind = if let Some(each) = each {
+            if default.is_some() {
+                return Err(Error::new_spanned(
+                    ident,
+                    "`builder(each = \"...\")` fields start out empty and cannot have a default",
+                ));
+            }
+            match inner_type(ty, "Vec") {
+                Some(inner) => Kind::Repeated(inner, each),
+                None => {
+                    return Err(Error::new_spanned(
+                        ty,
+                        "`builder(each = \"...\")` requires a field of type `Vec<T>`",
+                    ))
+                }
+            }
+        } else if let Some(inner) = inner_type(ty, "Option") {
+            Kind::Optional(inner)
+        } else {
+            Kind::Required
+        };
+
+        Ok(Field {
+            ident,
+            ty,
+            kind,
+            field_attrs,
+            default,
+        })
+    }
+
+    fn storage(&self) -> TokenStream2 {
+        let Field {
+            ident,
+            ty,
+            field_attrs,
+            ..
+        } = self;
+        let ty = match &self.kind {
+            Kind::Required => quote!(::std::option::Option<#ty>),
+            Kind::Optional(_) | Kind::Repeated(..) => quote!(#ty),
+        };
+        quote! {
+            #(#[#field_attrs])*
+            #ident: #ty
+        }
+    }
+
+    fn initializer(&self) -> TokenStream2 {
+        let Field { ident, .. } = self;
+        let value = match &self.kind {
+            Kind::Required | Kind::Optional(_) => quote!(::std::option::Option::None),
+            Kind::Repeated(..) => quote!(::std::vec::Vec::new()),
+        };
+        quote!(#ident: #value)
+    }
+
+    fn setters(&self) -> TokenStream2 {
+        let Field { ident, ty, .. } = self;
+        match &self.kind {
+            Kind::Required => quote! {
+                pub fn #ident(&mut self, #ident: #ty) -> &mut Self {
+                    self.#ident = ::std::option::Option::Some(#ident);
+                    self
+                }
+            },
+            Kind::Optional(inner) => quote! {
+                pub fn #ident(&mut self, #ident: #inner) -> &mut Self {
+                    self.#ident = ::std::option::Option::Some(#ident);
+                    self
+                }
+            },
+            Kind::Repeated(inner, each) => {
+                let one_at_a_time = quote! {
+                    pub fn #each(&mut self, #each: #inner) -> &mut Self {
+                        self.#ident.push(#each);
+                        self
+                    }
+                };
+                if each == *ident {
+                    one_at_a_time
+                } else {
+                    quote! {
+                        #one_at_a_time
+
+                        pub fn #ident(&mut self, #ident: #ty) -> &mut Self {
+                            self.#ident = #ident;
+                            self
+                        }
+                    }
+                }
+            }
+        }
+    }
+
+    /// Fails if a field without a default was never set. Every check runs
+    /// before any field is moved out of the builder, so that a failed
+    /// `build()` leaves the builder as it was for the caller to fix and retry.
+    fn check(&self) -> Option<TokenStream2> {
+        let (Kind::Required, None) = (&self.kind, &self.default) else {
+            return None;
+        };
+        let Field { ident, .. } = self;
+        let msg = format!("field `{}` is not set", ident);
+        Some(quote! {
+            if self.#ident.is_none() {
+                return ::std::result::Result::Err(
+                    ::std::boxed::Box::<dyn ::std::error::Error>::from(#msg),
+                );
+            }
+        })
+    }
+
+    /// Runs a `default_with` function up front, while the builder is still
+    /// exactly as the caller left it, but only if the field was never set.
+    fn lazy_default(&self) -> Option<TokenStream2> {
+        let Some(DefaultValue::With(function)) = &self.default else {
+            return None;
+        };
+        let Field { ident, ty, .. } = self;
+        let var = default_var(ident);
+        Some(quote! {
+            let #var: ::std::option::Option<#ty> = if self.#ident.is_none() {
+                ::std::option::Option::Some(#function(self)?)
+            } else {
+                ::std::option::Option::None
+            };
+        })
+    }
+
+    /// Moves the field's final value out of the builder into a local of the
+    /// same name.
+    fn resolve(&self, fields: &[Field]) -> TokenStream2 {
+        let Field { ident, ty, .. } = self;
+        let value = match (&self.kind, &self.default) {
+            (Kind::Required, None) => quote!(self.#ident.take().unwrap()),
+            (Kind::Optional(_), None) => quote!(self.#ident.take()),
+            (Kind::Repeated(..), _) => quote!(::std::mem::take(&mut self.#ident)),
+            (kind, Some(default)) => {
+                let set = match kind {
+                    Kind::Optional(_) => quote!(::std::option::Option::Some(value)),
+                    _ => quote!(value),
+                };
+                let unset = match default {
+                    DefaultValue::Expr {
+                        expr, dependencies, ..
+                    } => {
+                        let dependencies = fields
+                            .iter()
+                            .filter(|field| dependencies.contains(field.ident))
+                            .map(|field| field.ident);
+                        quote! {
+                            {
+                                #(let #dependencies = &#dependencies;)*
+                                #expr
+                            }
+                        }
+                    }
+                    DefaultValue::With(_) => {
+                        let var = default_var(ident);
+                        quote!(#var.unwrap())
+                    }
+                };
+                quote! {
+                    match self.#ident.take() {
+                        ::std::option::Option::Some(value) => #set,
+                        ::std::option::Option::None => #unset,
+                    }
+                }
+            }
+        };
+        quote! {
+            let #ident: #ty = #value;
+        }
+    }
+}
+
+fn default_var(ident: &Ident) -> Ident {
+    format_ident!("__default_{}", ident)
+}
+
+/// Orders fields so that every `default` expression comes after the fields it
+/// refers to, keeping declaration order wherever the dependencies allow.
+fn resolution_order(fields: &[Field]) -> Result<Vec<usize>> {
+    let dependencies = fields
+        .iter()
+        .map(|field| match &field.default {
+            Some(DefaultValue::Expr { dependencies, .. }) => dependencies
+                .iter()
+                .filter_map(|dep| fields.iter().position(|field| field.ident == dep))
+                .collect(),
+            _ => Vec::new(),
+        })
+        .collect::<Vec<Vec<usize>>>();
+
+    let mut order = Vec::with_capacity(fields.len());
+    let mut resolved = vec![false; fields.len()];
+    while order.len() < fields.len() {
+        let next = (0..fields.len())
+            .find(|&i| !resolved[i] && dependencies[i].iter().all(|&dep| resolved[dep]));
+        match next {
+            Some(i) => {
+                resolved[i] = true;
+                order.push(i);
+            }
+            None => return Err(cycle_error(fields, &dependencies, &resolved)),
+        }
+    }
+    Ok(order)
+}
+
+fn cycle_error(fields: &[Field], dependencies: &[Vec<usize>], resolved: &[bool]) -> Error {
+    // Every unresolved field waits on another unresolved field, so walking
+    // those edges from any of them must eventually revisit a field.
+    let mut path = vec![resolved.iter().position(|done| !done).unwrap()];
+    loop {
+        let last = *path.last().unwrap();
+        let next = *dependencies[last]
+            .iter()
+            .find(|&&dep| !resolved[dep])
+            .unwrap();
+        if let Some(start) = path.iter().position(|&i| i == next) {
+            path.drain(..start);
+            path.push(next);
+            break;
+        }
+        path.push(next);
+    }
+
+    let cycle = path
+        .iter()
+        .map(|&i| format!("`{}`", fields[i].ident))
+        .collect::<Vec<_>>()
+        .join(" -> ");
+    let mut errors = Errors::default();
+    for &i in &path[..path.len() - 1] {
+        if let Some(DefaultValue::Expr { lit, .. }) = &fields[i].default {
+            let msg = format!("cyclic builder defaults: {}", cycle);
+            errors.push(Error::new(lit.span(), msg));
+        }
+    }
+    errors.finish().unwrap_err()
+}
+
+/// Collects every identifier in a default expression that names one of the
+/// struct's fields, including identifiers inside macro invocations such as
+/// `format!` and the names that a string literal inside one captures, as in
+/// `format!("{executable}")`.
+struct FieldRefs<'a> {
+    fields: &'a [Field<'a>],
+    found: Vec<Ident>,
+}
+
+impl FieldRefs<'_> {
+    fn add(&mut self, name: &str) {
+        if let Some(field) = self.fields.iter().find(|field| field.ident == name) {
+            if !self.found.contains(field.ident) {
+                self.found.push(field.ident.clone());
+            }
+        }
+    }
+
+    fn add_tokens(&mut self, tokens: TokenStream2) {
+        for token in tokens {
+            match token {
+                TokenTree::Ident(ident) => self.add(&ident.to_string()),
+                TokenTree::Group(group) => self.add_tokens(group.stream()),
+                TokenTree::Literal(lit) => {
+                    if let Lit::Str(lit) = Lit::new(lit) {
+                        self.add_captures(&lit.value());
+                    }
+                }
+                TokenTree::Punct(_) => {}
+            }
+        }
+    }
+
+    /// Adds the names that a format string captures: the argument of a
+    /// placeholder such as `{executable}` or `{executable:?}`, and counts
+    /// such as `{:width$}`. Strings that are not format strings are scanned
+    /// too, which at worst orders the fields more strictly than needed.
+    fn add_captures(&mut self, template: &str) {
+        let mut rest = template;
+        while let Some(start) = rest.find('{') {
+            rest = &rest[start + 1..];
+            if let Some(escaped) = rest.strip_prefix('{') {
+                rest = escaped;
+                continue;
+            }
+            let end = rest.find('}').unwrap_or(rest.len());
+            let (arg, spec) = rest[..end].split_once(':').unwrap_or((&rest[..end], ""));
+            self.add(arg.trim());
+            for (dollar, _) in spec.match_indices('$') {
+                let count = spec[..dollar]
+                    .rsplit(|c: char| !(c.is_alphanumeric() || c == '_'))
+                    .next();
+                self.add(count.unwrap_or_default());
+            }
+            rest = &rest[end..];
+        }
+    }
+}
+
+impl<'ast> Visit<'ast> for FieldRefs<'_> {
+    fn visit_expr_path(&mut self, expr: &'ast ExprPath) {
+        if expr.qself.is_none() {
+            if let Some(ident) = expr.path.get_ident() {
+                self.add(&ident.to_string());
+            }
+        }
+        visit::visit_expr_path(self, expr);
+    }
+
+    fn visit_macro(&mut self, mac: &'ast Macro) {
+        self.add_tokens(mac.tokens.clone());
+    }
+}
+
+/// Keys accepted inside `#[builder(...)]` on a field.
+const KEYS: &[&str] = &["default", "default_with", "each", "field_attrs"];
+
+fn unknown_key(path: &Path) -> Error {
+    let key = path.to_token_stream().to_string().replace(' ', "");
+    let expected = KEYS
+        .iter()
+        .map(|key| format!("`{}`", key))
+        .collect::<Vec<_>>()
+        .join(", ");
+    let msg = match closest_key(&key) {
+        Some(suggestion) => format!(
+            "unknown builder key `{}`, did you mean `{}`? expected one of {}",
+            key, suggestion, expected,
+        ),
+        None => format!(
+            "unknown builder key `{}`, expected one of {}",
+            key, expected,
+        ),
+    };
+    Error::new_spanned(path, msg)
+}
+
+/// Picks the known key nearest to `key` by edit distance, if any is close
+/// enough to plausibly be a typo.
+fn closest_key(key: &str) -> Option<&'static str> {
+    let threshold = key.chars().count().max(3) / 3;
+    KEYS.iter()
+        .map(|candidate| (edit_distance(key, candidate), *candidate))
+        .filter(|(distance, _)| *distance <= threshold)
+        .min()
+        .map(|(_, candidate)| candidate)
+}
+
+fn edit_distance(a: &str, b: &str) -> usize {
+    let b: Vec<char> = b.chars().collect();
+    let mut row: Vec<usize> = (0..=b.len()).collect();
+    for (i, ca) in a.chars().enumerate() {
+        let mut diagonal = row[0];
+        row[0] = i + 1;
+        for (j, cb) in b.iter().enumerate() {
+            let substitution = diagonal + usize::from(ca != *cb);
+            diagonal = row[j + 1];
+            row[j + 1] = substitution.min(row[j] + 1).min(diagonal + 1);
+        }
+    }
+    row[b.len()]
+}
+
+/// Accumulates errors so that independent mistakes are all reported by a
+/// single expansion rather than one per compile.
+#[derive(Default)]
+struct Errors(Option<Error>);
+
+impl Errors {
+    fn push(&mut self, error: Error) {
+        match &mut self.0 {
+            Some(errors) => errors.combine(error),
+            None => self.0 = Some(error),
+        }
+    }
+
+    fn ok<T>(&mut self, result: Result<T>) -> Option<T> {
+        result.map_err(|error| self.push(error)).ok()
+    }
+
+    fn finish(self) -> Result<()> {
+        match self.0 {
+            Some(error) => Err(error),
+            None => Ok(()),
+        }
+    }
+}
+
+/// Returns `T` if `ty` is spelled `wrapper<T>`, e.g. `Option<T>` or `Vec<T>`.
+fn inner_type<'a>(ty: &'a Type, wrapper: &str) -> Option<&'a Type> {
+    let Type::Path(ty) = ty else {
+        return None;
+    };
+    if ty.qself.is_some() {
+        return None;
+    }
+    let segment = ty.path.segments.last()?;
+    if segment.ident != wrapper {
+        return None;
+    }
+    let PathArguments::AngleBracketed(args) = &segment.arguments else {
+        return None;
+    };
+    match args.args.first()? {
+        GenericArgument::Type(inner) if args.args.len() == 1 => Some(inner),
+        _ => None,
+    }
 }
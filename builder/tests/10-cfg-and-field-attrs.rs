@@ -0,0 +1,45 @@
+// Fields behind a disabled `#[cfg(...)]` are removed by the compiler before
+// any derive runs, so the derive never sees them and there is nothing for it
+// to do: no storage, setter or line in `build()` is generated for them, and a
+// disabled field may even name a type that does not exist or carry builder
+// attributes that would otherwise be rejected. Enabled fields are seen with
+// their cfg already gone and are handled like any other field.
+//
+// Other attributes can be forwarded onto the builder's storage field with
+// `#[builder(field_attrs(...))]`. Each entry is emitted as its own attribute:
+//
+//     pub struct CommandBuilder {
+//         #[doc = "Name or path of the program to run."]
+//         #[allow(dead_code)]
+//         executable: Option<String>,
+//         args: Vec<String>,
+//         ...
+//     }
+
+use derive_builder::Builder;
+
+#[derive(Builder)]
+pub struct Command {
+    #[builder(field_attrs(doc = "Name or path of the program to run.", allow(dead_code)))]
+    executable: String,
+    #[cfg(all())]
+    #[builder(each = "arg")]
+    args: Vec<String>,
+    #[cfg(any())]
+    #[builder(each = "env")]
+    env: Vec<TypeThatDoesNotExist>,
+    #[cfg(any())]
+    #[builder(not_a_builder_key)]
+    current_dir: TypeThatDoesNotExist,
+}
+
+fn main() {
+    let command = Command::builder()
+        .executable("cargo".to_owned())
+        .arg("build".to_owned())
+        .build()
+        .unwrap();
+
+    assert_eq!(command.executable, "cargo");
+    assert_eq!(command.args, vec!["build"]);
+}
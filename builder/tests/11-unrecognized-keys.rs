@@ -0,0 +1,16 @@
+// Every misspelled key in the struct is reported in one go, each with its span
+// on the key alone. Keys that are close to a real one come with a suggestion.
+
+use derive_builder::Builder;
+
+#[derive(Builder)]
+pub struct Command {
+    executable: String,
+    #[builder(each = "arg", colour = "red")]
+    args: Vec<String>,
+    #[builder(feild_attrs(allow(dead_code)))]
+    env: Vec<String>,
+    current_dir: Option<String>,
+}
+
+fn main() {}
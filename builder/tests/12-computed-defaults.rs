@@ -0,0 +1,88 @@
+// Fields may be given a default that is used when their setter was never
+// called. A `default` expression can mention other fields of the struct by
+// name; inside the expression each of them is a reference to that field's
+// final value. The derive resolves fields in declaration order, except that a
+// field is always resolved after the fields its default refers to. That
+// includes fields captured by name in a format string, as in
+// `format!("{executable}")`, or used as a width or precision, as in
+// `{:>width$}`.
+//
+// A `default_with` function receives the builder exactly as the caller left
+// it and returns a `Result`, whose error is propagated out of `build()`.
+//
+//     fn build(&mut self) -> Result<Command, Box<dyn Error>> {
+//         if self.executable.is_none() {
+//             return Err(Box::from("field `executable` is not set"));
+//         }
+//         let __default_env = if self.env.is_none() {
+//             Some(default_env(self)?)
+//         } else {
+//             None
+//         };
+//         let executable: String = self.executable.take().unwrap();
+//         let current_dir: Option<String> = match self.current_dir.take() {
+//             Some(value) => Some(value),
+//             None => {
+//                 let executable = &executable;
+//                 Some(format!("/opt/{}", executable))
+//             }
+//         };
+//         ...
+//     }
+
+use derive_builder::Builder;
+
+#[derive(Builder)]
+pub struct Command {
+    #[builder(default = "format!(\"/run/{executable}.{pid:0>width$}\")")]
+    pid_file: String,
+    #[builder(default = "log_dir.join(\"run.log\")")]
+    log_file: std::path::PathBuf,
+    executable: String,
+    #[builder(default = "std::path::PathBuf::from(current_dir.as_deref().unwrap_or(\"/tmp\"))")]
+    log_dir: std::path::PathBuf,
+    #[builder(default = "Some(format!(\"/opt/{}\", executable))")]
+    current_dir: Option<String>,
+    #[builder(default_with = "default_env")]
+    env: Vec<String>,
+    #[builder(default = "1")]
+    pid: u32,
+    #[builder(default = "4")]
+    width: usize,
+}
+
+fn default_env(builder: &CommandBuilder) -> Result<Vec<String>, String> {
+    match &builder.executable {
+        Some(executable) if executable.is_empty() => Err("empty executable".to_owned()),
+        Some(executable) => Ok(vec![format!("PROGRAM={}", executable)]),
+        None => Err("no executable".to_owned()),
+    }
+}
+
+fn main() {
+    let command = Command::builder()
+        .executable("cargo".to_owned())
+        .build()
+        .unwrap();
+    assert_eq!(command.current_dir.as_deref(), Some("/opt/cargo"));
+    assert_eq!(command.log_dir, std::path::Path::new("/opt/cargo"));
+    assert_eq!(command.log_file, std::path::Path::new("/opt/cargo/run.log"));
+    assert_eq!(command.env, vec!["PROGRAM=cargo"]);
+    assert_eq!(command.pid_file, "/run/cargo.0001");
+
+    let command = Command::builder()
+        .executable("cargo".to_owned())
+        .current_dir("..".to_owned())
+        .env(vec![])
+        .build()
+        .unwrap();
+    assert_eq!(command.log_file, std::path::Path::new("../run.log"));
+    assert!(command.env.is_empty());
+
+    let err = Command::builder()
+        .executable(String::new())
+        .build()
+        .err()
+        .unwrap();
+    assert_eq!(err.to_string(), "empty executable");
+}
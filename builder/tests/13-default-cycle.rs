@@ -0,0 +1,16 @@
+// Defaults that depend on each other in a loop have no order in which they
+// could be computed. This is reported when the macro expands rather than left
+// to surface as a use of an undeclared variable.
+
+use derive_builder::Builder;
+
+#[derive(Builder)]
+pub struct Command {
+    executable: String,
+    #[builder(default = "format!(\"{}/bin\", current_dir)")]
+    bin_dir: String,
+    #[builder(default = "format!(\"{}/..\", bin_dir)")]
+    current_dir: String,
+}
+
+fn main() {}
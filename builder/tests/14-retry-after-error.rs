@@ -0,0 +1,42 @@
+// A failed `build()` must leave the builder as it was, so that the caller can
+// set the missing field and call `build()` again. Every required field is
+// checked before any value is moved out of the builder:
+//
+//     fn build(&mut self) -> Result<Command, Box<dyn Error>> {
+//         if self.executable.is_none() {
+//             return Err(Box::from("field `executable` is not set"));
+//         }
+//         if self.current_dir.is_none() {
+//             return Err(Box::from("field `current_dir` is not set"));
+//         }
+//         let executable: String = self.executable.take().unwrap();
+//         ...
+//     }
+
+use derive_builder::Builder;
+
+#[derive(Builder)]
+pub struct Command {
+    executable: String,
+    #[builder(each = "arg")]
+    args: Vec<String>,
+    env: Option<Vec<String>>,
+    current_dir: String,
+}
+
+fn main() {
+    let mut builder = Command::builder();
+    builder.executable("cargo".to_owned());
+    builder.arg("build".to_owned());
+    builder.env(vec!["RUST_LOG=debug".to_owned()]);
+
+    let err = builder.build().err().unwrap();
+    assert_eq!(err.to_string(), "field `current_dir` is not set");
+
+    builder.current_dir("..".to_owned());
+    let command = builder.build().unwrap();
+    assert_eq!(command.executable, "cargo");
+    assert_eq!(command.args, vec!["build"]);
+    assert_eq!(command.env.unwrap(), vec!["RUST_LOG=debug"]);
+    assert_eq!(command.current_dir, "..");
+}
@@ -0,0 +1,377 @@
+//! A view of the derive input with the `#[debug(...)]` attributes already
+//! parsed, shared by everything that generates code from it.
+
+use crate::attr::{self, ContainerAttrs, FieldAttrs, Print};
+use crate::format::{self, Argument, ArgumentKind};
+use syn::spanned::Spanned;
+use syn::{
+    Attribute, Data, DeriveInput, Error, Fields, Generics, Ident, Index, LitStr, Member, Result,
+    Type,
+};
+
+pub struct Input<'a> {
+    pub ident: &'a Ident,
+    pub generics: &'a Generics,
+    pub attrs: ContainerAttrs,
+    pub body: Body<'a>,
+}
+
+pub enum Body<'a> {
+    Struct(Variant<'a>),
+    Enum(Vec<Variant<'a>>),
+    /// A union, whose only field is the one named by
+    /// `debug(unsafe_union_field)`, or which has no fields if it is printed
+    /// opaquely or as raw bytes.
+    Union(Variant<'a>),
+}
+
+/// A struct body or one enum variant.
+pub struct Variant<'a> {
+    pub ident: &'a Ident,
+    /// Attributes as written, on the variant or, for a struct, on the struct.
+    pub original_attrs: &'a [Attribute],
+    /// The name to print, which is the identifier unless renamed.
+    pub name: String,
+    pub style: Style,
+    pub fields: Vec<Field<'a>>,
+    /// Indices into `fields` in the order they are printed.
+    pub order: Vec<usize>,
+    pub binding: Binding,
+}
+
+/// How the fields of a struct or variant are bound to `__self_{i}`.
+#[derive(Copy, Clone, PartialEq)]
+pub enum Binding {
+    /// By reference, with a pattern.
+    Ref,
+    /// By reference to a copy, for `#[repr(packed)]` structs whose fields may
+    /// be unaligned.
+    Copy,
+    /// By reference, from the field of a union, which is unsafe to read.
+    Union,
+}
+
+#[derive(Copy, Clone, PartialEq)]
+pub enum Style {
+    Named,
+    Tuple,
+    Unit,
+}
+
+pub struct Field<'a> {
+    pub member: Member,
+    /// The name to print for a named field, which is the identifier unless
+    /// renamed.
+    pub name: String,
+    pub ty: &'a Type,
+    pub attrs: FieldAttrs,
+}
+
+impl<'a> Input<'a> {
+    pub fn from_syn(input: &'a DeriveInput) -> Result<Self> {
+        let attrs = attr::container(&input.attrs)?;
+        let mut body = match &input.data {
+            Data::Struct(data) => {
+                let mut variant = Variant::from_syn(
+                    &input.ident,
+                    &input.attrs,
+                    attrs.name.as_ref(),
+                    Style::of(&data.fields),
+                    &data.fields,
+                )?;
+                if attr::packed(&input.attrs) {
+                    variant.binding = Binding::Copy;
+                }
+                Body::Struct(variant)
+            }
+            Data::Enum(data) => {
+                if let Some(name) = &attrs.name {
+                    return Err(Error::new_spanned(
+                        name,
+                        "an enum prints as its variants, put `debug(name = \"...\")` on a variant",
+                    ));
+                }
+                Body::Enum(
+                    data.variants
+                        .iter()
+                        .map(|variant| {
+                            let attrs = attr::variant(&variant.attrs)?;
+                            Variant::from_syn(
+                                &variant.ident,
+                                &variant.attrs,
+                                attrs.name.as_ref(),
+                                Style::of(&variant.fields),
+                                &variant.fields,
+                            )
+                        })
+                        .collect::<Result<_>>()?,
+                )
+            }
+            Data::Union(data) => {
+                let mut variant = Variant::from_syn(
+                    &input.ident,
+                    &input.attrs,
+                    attrs.name.as_ref(),
+                    Style::Named,
+                    &data.fields.named,
+                )?;
+                let active = match &attrs.unsafe_union_field {
+                    Some(name) => match variant.field_named(&name.value()) {
+                        Some(i) => Some(variant.fields.swap_remove(i)),
+                        None => {
+                            return Err(Error::new_spanned(
+                                name,
+                                format!("no field `{}` in `{}`", name.value(), input.ident),
+                            ))
+                        }
+                    },
+                    None => None,
+                };
+                variant.fields = active.into_iter().collect();
+                variant.binding = Binding::Union;
+                Body::Union(variant)
+            }
+        };
+        if let (Some(union_field), false) =
+            (&attrs.unsafe_union_field, matches!(body, Body::Union(_)))
+        {
+            return Err(Error::new_spanned(
+                union_field,
+                "`debug(unsafe_union_field)` picks the field of a union to print",
+            ));
+        }
+        match (&attrs.unsafe_raw_bytes, &attrs.unsafe_union_field, &body) {
+            (Some(raw_bytes), _, Body::Struct(_) | Body::Enum(_)) => {
+                return Err(Error::new_spanned(
+                    raw_bytes,
+                    "`debug(unsafe_raw_bytes)` prints the bytes of a union",
+                ));
+            }
+            (Some(raw_bytes), Some(_), _) => {
+                return Err(Error::new_spanned(
+                    raw_bytes,
+                    "conflicting debug attributes, a union is printed either as `unsafe_union_field` or as `unsafe_raw_bytes`",
+                ));
+            }
+            _ => {}
+        }
+        match &mut body {
+            Body::Struct(variant) | Body::Union(variant) => {
+                if let (Some(sort), Style::Tuple) = (&attrs.sort_fields, variant.style) {
+                    return Err(Error::new_spanned(
+                        sort,
+                        "tuple fields are printed by position, `debug(sort_fields)` requires named fields",
+                    ));
+                }
+                variant.order_fields(attrs.sort_fields.is_some());
+            }
+            Body::Enum(variants) => {
+                for variant in variants {
+                    variant.order_fields(attrs.sort_fields.is_some());
+                }
+            }
+        }
+        if let Some(discriminant) = &attrs.discriminant {
+            let variants = match &body {
+                Body::Enum(variants) => variants,
+                Body::Struct(_) | Body::Union(_) => {
+                    return Err(Error::new_spanned(
+                        &discriminant.path,
+                        "`debug(discriminant)` prints the discriminants of an enum's variants",
+                    ))
+                }
+            };
+            let with_fields = variants.iter().find(|variant| variant.style != Style::Unit);
+            if let (Some(variant), None) = (with_fields, &discriminant.repr) {
+                return Err(Error::new_spanned(
+                    variant.ident,
+                    "`debug(discriminant)` on an enum with fields requires an integer \
+                     representation to read the discriminant from, as in `#[repr(u8)]`",
+                ));
+            }
+        }
+        if let Some(extra) = attrs.extras.first() {
+            let named_struct =
+                matches!(&body, Body::Struct(variant) if variant.style == Style::Named);
+            if !named_struct || attrs.transparent.is_some() {
+                return Err(Error::new_spanned(
+                    &extra.name,
+                    "`debug(extra)` adds named entries, which requires a struct with named fields",
+                ));
+            }
+        }
+        if let Some(transparent) = &attrs.transparent {
+            let single_field = match &body {
+                Body::Struct(variant) => {
+                    variant.fields.len() == 1
+                        && !matches!(variant.fields[0].attrs.print, Print::Skip)
+                }
+                Body::Enum(_) | Body::Union(_) => false,
+            };
+            if !single_field {
+                return Err(Error::new_spanned(
+                    transparent,
+                    "`debug(transparent)` requires a struct with exactly one field",
+                ));
+            }
+        }
+        Ok(Input {
+            ident: &input.ident,
+            generics: &input.generics,
+            attrs,
+            body,
+        })
+    }
+
+    pub fn variants(&self) -> &[Variant<'a>] {
+        match &self.body {
+            Body::Struct(variant) | Body::Union(variant) => std::slice::from_ref(variant),
+            Body::Enum(variants) => variants,
+        }
+    }
+
+    pub fn fields(&self) -> impl Iterator<Item = &Field<'a>> {
+        self.variants().iter().flat_map(|variant| &variant.fields)
+    }
+}
+
+impl Style {
+    fn of(fields: &Fields) -> Self {
+        match fields {
+            Fields::Named(_) => Style::Named,
+            Fields::Unnamed(_) => Style::Tuple,
+            Fields::Unit => Style::Unit,
+        }
+    }
+}
+
+impl<'a> Variant<'a> {
+    fn from_syn(
+        ident: &'a Ident,
+        original_attrs: &'a [Attribute],
+        name: Option<&LitStr>,
+        style: Style,
+        fields: impl IntoIterator<Item = &'a syn::Field>,
+    ) -> Result<Self> {
+        let fields = fields
+            .into_iter()
+            .enumerate()
+            .map(|(i, field)| {
+                let attrs = attr::field(&field.attrs)?;
+                let (member, name) = match &field.ident {
+                    Some(ident) => (Member::Named(ident.clone()), ident.to_string()),
+                    None => (Member::Unnamed(Index::from(i)), i.to_string()),
+                };
+                if let (Member::Unnamed(_), Print::Flatten) = (&member, &attrs.print) {
+                    return Err(Error::new(
+                        field.ty.span(),
+                        "`debug(flatten)` inlines named entries, which requires a named field",
+                    ));
+                }
+                if let (Member::Unnamed(_), Some((_, order))) = (&member, &attrs.order) {
+                    return Err(Error::new_spanned(
+                        order,
+                        "tuple fields are printed by position, `debug(order)` has no effect",
+                    ));
+                }
+                if let (Member::Unnamed(_), Some(rename)) = (&member, &attrs.rename) {
+                    return Err(Error::new_spanned(
+                        rename,
+                        "tuple fields are printed without names, `debug(rename)` has no effect",
+                    ));
+                }
+                Ok(Field {
+                    member,
+                    name: attrs.rename.as_ref().map_or(name, LitStr::value),
+                    ty: &field.ty,
+                    attrs,
+                })
+            })
+            .collect::<Result<_>>()?;
+        let variant = Variant {
+            ident,
+            original_attrs,
+            name: name.map_or_else(|| ident.to_string(), LitStr::value),
+            style,
+            order: Vec::new(),
+            fields,
+            binding: Binding::Ref,
+        };
+        variant.check_sibling_references()?;
+        Ok(variant)
+    }
+
+    /// Puts the fields with `debug(order = N)` first, by N, and then the rest
+    /// in declaration order or, if `sort` is set and the fields are named,
+    /// alphabetically.
+    fn order_fields(&mut self, sort: bool) {
+        let sort = sort && self.style == Style::Named;
+        let fields = &self.fields;
+        self.order = (0..fields.len()).collect();
+        self.order.sort_by(|&a, &b| {
+            let key = |i: usize| {
+                let field = &fields[i];
+                let order = field.attrs.order.as_ref().map(|(order, _)| *order);
+                let name = if sort { Some(&field.name) } else { None };
+                (order.is_none(), order, name)
+            };
+            key(a).cmp(&key(b))
+        });
+    }
+
+    /// The index of the named field called `name`.
+    pub fn field_named(&self, name: &str) -> Option<usize> {
+        self.fields
+            .iter()
+            .position(|field| matches!(&field.member, Member::Named(ident) if ident == name))
+    }
+
+    /// Error for a template argument that refers to no field of this variant.
+    pub fn unknown_field(&self, arg: &Argument, lit: &LitStr) -> Error {
+        let fields = self
+            .fields
+            .iter()
+            .map(|field| match &field.member {
+                Member::Named(ident) => format!("`{}`", ident),
+                Member::Unnamed(index) => format!("`{}`", index.index),
+            })
+            .collect::<Vec<_>>();
+        let expected = if fields.is_empty() {
+            format!("`{}` has no fields", self.ident)
+        } else {
+            format!("expected one of {}", fields.join(", "))
+        };
+        let msg = match &arg.kind {
+            ArgumentKind::Name(name) => {
+                format!("no field `{}` in `{}`, {}", name, self.ident, expected)
+            }
+            ArgumentKind::Index(index) => {
+                format!("no field `{}` in `{}`, {}", index, self.ident, expected)
+            }
+            ArgumentKind::Next => format!(
+                "`{{}}` does not say which field to print, name one inside the braces; {}",
+                expected,
+            ),
+        };
+        format::error_at(lit, arg.range.clone(), msg)
+    }
+
+    /// Checks that the names in field format strings are fields of this
+    /// variant.
+    fn check_sibling_references(&self) -> Result<()> {
+        for field in &self.fields {
+            let format = match &field.attrs.print {
+                Print::Format(format) => format,
+                _ => continue,
+            };
+            for arg in format.template.arguments() {
+                if let ArgumentKind::Name(name) = &arg.kind {
+                    if self.field_named(name).is_none() {
+                        return Err(self.unknown_field(arg, &format.lit));
+                    }
+                }
+            }
+        }
+        Ok(())
+    }
+}
@@ -0,0 +1,553 @@
+use crate::format::{self, ArgumentKind, Count, FormatString};
+use proc_macro2::Span;
+use quote::ToTokens;
+use syn::meta::ParseNestedMeta;
+use syn::punctuated::Punctuated;
+use syn::spanned::Spanned;
+use syn::{
+    parenthesized, token, Attribute, Error, Expr, ExprLit, Ident, Lit, LitInt, LitStr, Meta, Path,
+    Result, Token, WherePredicate,
+};
+
+/// Attributes on the struct or enum itself.
+#[derive(Default)]
+pub struct ContainerAttrs {
+    /// `#[debug(bound = "...")]`, replacing every inferred bound.
+    pub bound: Option<Punctuated<WherePredicate, Token![,]>>,
+    /// `#[debug(transparent)]`, printing a newtype as its only field.
+    pub transparent: Option<Path>,
+    /// `#[debug(name = "...")]`, the struct name to print.
+    pub name: Option<LitStr>,
+    /// `#[debug(unsafe_union_field = "...")]`, the field of a union to print,
+    /// as if the union were a struct with just that field. The field is read
+    /// whichever field was last written, so this is only sound if it always
+    /// holds a valid value of its type, as for unions of plain integers and
+    /// arrays that cover the union's whole size. The derive cannot check
+    /// that and the generated impl is safe to call, hence the `unsafe_`.
+    pub unsafe_union_field: Option<LitStr>,
+    /// `#[debug(unsafe_raw_bytes)]`, printing a union as its bytes in hex.
+    /// Every byte of the union is read, so this is only sound if all of them
+    /// are always initialized, as when every field covers the union's whole
+    /// size without padding, or when the union is zeroed or filled in by C
+    /// code. Without this or `unsafe_union_field`, a union prints as
+    /// `Name { .. }`.
+    pub unsafe_raw_bytes: Option<Path>,
+    /// `#[debug(sort_fields)]`, printing named fields in alphabetical order
+    /// of their printed names.
+    pub sort_fields: Option<Path>,
+    /// `#[debug(compact)]`, printing on one line even under `{:#?}`.
+    pub compact: Option<Path>,
+    /// `#[debug(discriminant)]` or `#[debug(discriminant = "hex")]`,
+    /// printing an enum's variants along with their discriminants.
+    pub discriminant: Option<Discriminant>,
+    /// `#[debug(extra(name = "...", expr = "..."))]`, entries printed after
+    /// the fields, in the order written.
+    pub extras: Vec<Extra>,
+}
+
+pub struct Discriminant {
+    pub path: Path,
+    /// Printed as `Name = 0x01` rather than `Name(1)`.
+    pub hex: bool,
+    /// The integer type of `#[repr(...)]`, if the enum has one. The
+    /// discriminant is stored in it, first, even when variants have fields.
+    pub repr: Option<Ident>,
+}
+
+/// A computed entry that is printed like a field but is not stored in one.
+pub struct Extra {
+    pub name: LitStr,
+    /// Evaluated inside `fmt`, where `self` is in scope.
+    pub expr: Expr,
+}
+
+/// Attributes on an enum variant.
+#[derive(Default)]
+pub struct VariantAttrs {
+    /// `#[debug(name = "...")]`, the variant name to print.
+    pub name: Option<LitStr>,
+}
+
+/// Attributes on a single field.
+#[derive(Default)]
+pub struct FieldAttrs {
+    pub print: Print,
+    /// `#[debug(bound = "...")]`, replacing the bounds inferred from this
+    /// field's type.
+    pub bound: Option<Punctuated<WherePredicate, Token![,]>>,
+    /// `#[debug(rename = "...")]`, the field name to print.
+    pub rename: Option<LitStr>,
+    /// `#[debug(order = N)]`, the field's position in the output. Fields
+    /// with an order are printed first, lowest first.
+    pub order: Option<(i64, LitInt)>,
+    /// `#[debug(skip_if = "...")]`, a predicate taking `&T` which leaves the
+    /// field out of the output when it returns true.
+    pub skip_if: Option<Path>,
+    /// `#[debug(expr = "...")]`, an expression printed in place of the
+    /// field's value. It is evaluated inside `fmt`, where `self` is in scope.
+    pub expr: Option<Expr>,
+    /// `#[debug(diff)]`, comparing the field with its `DebugDiff` impl in
+    /// `derive(DebugDiff)`, which the field type must then implement. Without
+    /// it, whether to recurse into a field is decided where the impl is
+    /// compiled, so a field whose type is a type parameter is compared by its
+    /// `Debug` output.
+    pub diff: Option<Path>,
+}
+
+/// How a field's value is printed. At most one of these may be chosen per
+/// field.
+#[derive(Default)]
+pub enum Print {
+    /// Through the field type's own `Debug` impl.
+    #[default]
+    Debug,
+    /// `#[debug = "..."]`, a format string applied to the field's value.
+    Format(FieldFormat),
+    /// `#[debug(redact)]` or `#[debug(redact = "...")]`, printing a fixed
+    /// placeholder in place of the value.
+    Redact(LitStr),
+    /// `#[debug(with = "...")]`, a function with the signature
+    /// `fn(&T, &mut fmt::Formatter) -> fmt::Result` that prints the value.
+    With(Path),
+    /// `#[debug(max_items = N)]`, printing at most N elements of an iterable
+    /// field followed by a count of the rest.
+    MaxItems(LitInt),
+    /// `#[debug(max_len = N)]`, printing at most N characters of a string or
+    /// N bytes of a byte slice followed by a count of the rest.
+    MaxLen(LitInt),
+    /// `#[debug(hex)]` or `#[debug(binary)]`, printing integers the way
+    /// `{:x?}` would, in the given radix, and arrays, slices, `Vec`s, `Box`es
+    /// and `Option`s of them element by element. Carries the span of the
+    /// key, where a field type that cannot be printed this way is reported.
+    Radix(Radix, Span),
+    /// `#[debug(bytes)]`, printing bytes as an escaped byte string literal.
+    Bytes,
+    /// `#[debug(hexdump)]`, printing bytes as hex, with offsets and an ASCII
+    /// column under `{:#?}`.
+    Hexdump,
+    /// `#[debug(flatten)]`, printing the entries of a nested struct that
+    /// also derives `CustomDebug` as if they were fields of this one.
+    Flatten,
+    /// `#[debug(skip)]`, leaving the field out altogether.
+    Skip,
+}
+
+/// A field's format string, in which `{}` or `{0}` is the field's value and
+/// `{name}` is the value of the sibling field called `name`.
+pub struct FieldFormat {
+    pub lit: LitStr,
+    pub template: FormatString,
+}
+
+#[derive(Copy, Clone)]
+pub enum Radix {
+    Hex,
+    Binary,
+}
+
+impl Print {
+    /// Whether the output involves the field type's `Debug` impl.
+    pub fn uses_debug(&self) -> bool {
+        matches!(self, Print::Debug | Print::MaxItems(_) | Print::Flatten)
+    }
+}
+
+/// Keys accepted inside `#[debug(...)]` on the struct or enum.
+const CONTAINER_KEYS: &[&str] = &[
+    "bound",
+    "compact",
+    "discriminant",
+    "extra",
+    "name",
+    "sort_fields",
+    "transparent",
+    "unsafe_raw_bytes",
+    "unsafe_union_field",
+];
+
+/// Keys accepted inside `#[debug(extra(...))]`.
+const EXTRA_KEYS: &[&str] = &["expr", "name"];
+
+/// Keys accepted inside `#[debug(...)]` on an enum variant.
+const VARIANT_KEYS: &[&str] = &["name"];
+
+/// Keys accepted inside `#[debug(...)]` on a field.
+const FIELD_KEYS: &[&str] = &[
+    "binary",
+    "bound",
+    "bytes",
+    "diff",
+    "expr",
+    "flatten",
+    "hex",
+    "hexdump",
+    "max_items",
+    "max_len",
+    "order",
+    "redact",
+    "rename",
+    "skip",
+    "skip_if",
+    "with",
+];
+
+fn unknown_key(meta: &ParseNestedMeta, keys: &[&str]) -> Error {
+    let expected = keys
+        .iter()
+        .map(|key| format!("`{}`", key))
+        .collect::<Vec<_>>()
+        .join(", ");
+    meta.error(format!(
+        "unknown debug attribute, expected one of {}",
+        expected
+    ))
+}
+
+pub fn container(attrs: &[Attribute]) -> Result<ContainerAttrs> {
+    let mut container = ContainerAttrs::default();
+    for attr in attrs {
+        if !attr.path().is_ident("debug") {
+            continue;
+        }
+        if let Meta::NameValue(_) = &attr.meta {
+            return Err(Error::new_spanned(
+                attr,
+                "format strings go on fields, expected `debug(bound = \"...\")`",
+            ));
+        }
+        attr.parse_nested_meta(|meta| {
+            if meta.path.is_ident("bound") {
+                let lit: LitStr = meta.value()?.parse()?;
+                container.bound = Some(lit.parse_with(Punctuated::parse_terminated)?);
+                Ok(())
+            } else if meta.path.is_ident("transparent") {
+                container.transparent = Some(meta.path);
+                Ok(())
+            } else if meta.path.is_ident("name") {
+                container.name = Some(meta.value()?.parse()?);
+                Ok(())
+            } else if meta.path.is_ident("unsafe_union_field") {
+                container.unsafe_union_field = Some(meta.value()?.parse()?);
+                Ok(())
+            } else if meta.path.is_ident("unsafe_raw_bytes") {
+                container.unsafe_raw_bytes = Some(meta.path);
+                Ok(())
+            } else if meta.path.is_ident("sort_fields") {
+                container.sort_fields = Some(meta.path);
+                Ok(())
+            } else if meta.path.is_ident("compact") {
+                container.compact = Some(meta.path);
+                Ok(())
+            } else if meta.path.is_ident("discriminant") {
+                let hex = if meta.input.peek(Token![=]) {
+                    let lit: LitStr = meta.value()?.parse()?;
+                    if lit.value() != "hex" {
+                        return Err(Error::new_spanned(
+                            lit,
+                            "expected `discriminant` or `discriminant = \"hex\"`",
+                        ));
+                    }
+                    true
+                } else {
+                    false
+                };
+                container.discriminant = Some(Discriminant {
+                    path: meta.path,
+                    hex,
+                    repr: repr_int(attrs),
+                });
+                Ok(())
+            } else if meta.path.is_ident("extra") {
+                container.extras.push(extra(&meta)?);
+                Ok(())
+            } else {
+                Err(unknown_key(&meta, CONTAINER_KEYS))
+            }
+        })?;
+    }
+    Ok(container)
+}
+
+/// Whether the type is `#[repr(packed)]` or `#[repr(packed(N))]`.
+pub fn packed(attrs: &[Attribute]) -> bool {
+    let mut packed = false;
+    for attr in attrs {
+        if !attr.path().is_ident("repr") {
+            continue;
+        }
+        // The compiler checks `repr` itself, so anything unexpected is left
+        // for it to report.
+        let _ = attr.parse_nested_meta(|meta| {
+            if meta.path.is_ident("packed") {
+                packed = true;
+            }
+            if meta.input.peek(token::Paren) {
+                let _content;
+                parenthesized!(_content in meta.input);
+            }
+            Ok(())
+        });
+    }
+    packed
+}
+
+/// The integer type in `#[repr(...)]`, if any.
+fn repr_int(attrs: &[Attribute]) -> Option<Ident> {
+    const INTS: &[&str] = &[
+        "i8", "i16", "i32", "i64", "i128", "isize", "u8", "u16", "u32", "u64", "u128", "usize",
+    ];
+    let mut repr = None;
+    for attr in attrs {
+        if !attr.path().is_ident("repr") {
+            continue;
+        }
+        let _ = attr.parse_nested_meta(|meta| {
+            if let Some(ident) = meta.path.get_ident() {
+                if INTS.iter().any(|int| ident == int) {
+                    repr = Some(ident.clone());
+                }
+            }
+            if meta.input.peek(token::Paren) {
+                let _content;
+                parenthesized!(_content in meta.input);
+            }
+            Ok(())
+        });
+    }
+    repr
+}
+
+fn extra(meta: &ParseNestedMeta) -> Result<Extra> {
+    let mut name = None;
+    let mut expr = None;
+    meta.parse_nested_meta(|meta| {
+        if meta.path.is_ident("name") {
+            name = Some(meta.value()?.parse()?);
+            Ok(())
+        } else if meta.path.is_ident("expr") {
+            let lit: LitStr = meta.value()?.parse()?;
+            expr = Some(lit.parse()?);
+            Ok(())
+        } else {
+            Err(unknown_key(&meta, EXTRA_KEYS))
+        }
+    })?;
+    match (name, expr) {
+        (Some(name), Some(expr)) => Ok(Extra { name, expr }),
+        _ => Err(meta.error("expected `extra(name = \"...\", expr = \"...\")`")),
+    }
+}
+
+pub fn variant(attrs: &[Attribute]) -> Result<VariantAttrs> {
+    let mut variant = VariantAttrs::default();
+    for attr in attrs {
+        if !attr.path().is_ident("debug") {
+            continue;
+        }
+        attr.parse_nested_meta(|meta| {
+            if meta.path.is_ident("name") {
+                variant.name = Some(meta.value()?.parse()?);
+                Ok(())
+            } else {
+                Err(unknown_key(&meta, VARIANT_KEYS))
+            }
+        })?;
+    }
+    Ok(variant)
+}
+
+pub fn field(attrs: &[Attribute]) -> Result<FieldAttrs> {
+    let mut field = FieldAttrs::default();
+    for attr in attrs {
+        if !attr.path().is_ident("debug") {
+            continue;
+        }
+        if let Meta::NameValue(nv) = &attr.meta {
+            match &nv.value {
+                Expr::Lit(ExprLit {
+                    lit: Lit::Str(lit), ..
+                }) => {
+                    let format = FieldFormat {
+                        lit: lit.clone(),
+                        template: check_format(lit)?,
+                    };
+                    set_print(&mut field, Print::Format(format), attr)?;
+                }
+                value => {
+                    return Err(Error::new_spanned(
+                        value,
+                        "expected a format string, as in `debug = \"0b{:08b}\"`",
+                    ))
+                }
+            }
+            continue;
+        }
+        attr.parse_nested_meta(|meta| {
+            if meta.path.is_ident("skip") {
+                set_print(&mut field, Print::Skip, &meta.path)
+            } else if meta.path.is_ident("hex") {
+                set_print(
+                    &mut field,
+                    Print::Radix(Radix::Hex, meta.path.span()),
+                    &meta.path,
+                )
+            } else if meta.path.is_ident("binary") {
+                set_print(
+                    &mut field,
+                    Print::Radix(Radix::Binary, meta.path.span()),
+                    &meta.path,
+                )
+            } else if meta.path.is_ident("bytes") {
+                set_print(&mut field, Print::Bytes, &meta.path)
+            } else if meta.path.is_ident("hexdump") {
+                set_print(&mut field, Print::Hexdump, &meta.path)
+            } else if meta.path.is_ident("flatten") {
+                set_print(&mut field, Print::Flatten, &meta.path)
+            } else if meta.path.is_ident("redact") {
+                let placeholder = if meta.input.peek(Token![=]) {
+                    meta.value()?.parse()?
+                } else {
+                    LitStr::new("<redacted>", meta.path.span())
+                };
+                set_print(&mut field, Print::Redact(placeholder), &meta.path)
+            } else if meta.path.is_ident("bound") {
+                let lit: LitStr = meta.value()?.parse()?;
+                field.bound = Some(lit.parse_with(Punctuated::parse_terminated)?);
+                Ok(())
+            } else if meta.path.is_ident("max_items") {
+                let max = meta.value()?.parse()?;
+                set_print(&mut field, Print::MaxItems(max), &meta.path)
+            } else if meta.path.is_ident("max_len") {
+                let max = meta.value()?.parse()?;
+                set_print(&mut field, Print::MaxLen(max), &meta.path)
+            } else if meta.path.is_ident("skip_if") {
+                let lit: LitStr = meta.value()?.parse()?;
+                field.skip_if = Some(lit.parse()?);
+                Ok(())
+            } else if meta.path.is_ident("expr") {
+                let lit: LitStr = meta.value()?.parse()?;
+                field.expr = Some(lit.parse()?);
+                Ok(())
+            } else if meta.path.is_ident("diff") {
+                field.diff = Some(meta.path);
+                Ok(())
+            } else if meta.path.is_ident("order") {
+                let lit: LitInt = meta.value()?.parse()?;
+                field.order = Some((lit.base10_parse()?, lit));
+                Ok(())
+            } else if meta.path.is_ident("rename") {
+                field.rename = Some(meta.value()?.parse()?);
+                Ok(())
+            } else if meta.path.is_ident("with") {
+                let lit: LitStr = meta.value()?.parse()?;
+                set_print(&mut field, Print::With(lit.parse()?), &meta.path)
+            } else {
+                Err(unknown_key(&meta, FIELD_KEYS))
+            }
+        })?;
+    }
+    if let Some(diff) = &field.diff {
+        if field.expr.is_some() || !matches!(field.print, Print::Debug) {
+            return Err(Error::new_spanned(
+                diff,
+                "`debug(diff)` compares the field's own value, it cannot be combined with a format, `expr`, `flatten` or `skip`",
+            ));
+        }
+    }
+    Ok(field)
+}
+
+fn set_print(field: &mut FieldAttrs, print: Print, tokens: impl ToTokens) -> Result<()> {
+    if let Print::Debug = field.print {
+        field.print = print;
+        Ok(())
+    } else {
+        Err(Error::new_spanned(
+            tokens,
+            "conflicting debug attributes, a field can only be printed one way",
+        ))
+    }
+}
+
+/// Checks a field's format string up front, where mistakes can be pointed out
+/// within the literal, rather than leaving them to `format_args!` in the
+/// generated code. The field's value is the one and only positional argument.
+/// Names are left to be checked against the sibling fields.
+fn check_format(lit: &LitStr) -> Result<FormatString> {
+    let template = format::parse(&lit.value()).map_err(|err| format::error(lit, err))?;
+    let mut next = 0;
+    let mut printed = false;
+    for placeholder in template.placeholders() {
+        let spec = &placeholder.spec;
+        let counts = [&spec.width, &spec.precision].into_iter().flatten();
+        let mut args = Vec::new();
+        for count in counts {
+            match count {
+                Count::Is(_) => {}
+                Count::Param(arg) => args.push(arg),
+                Count::Star => {
+                    next += 1;
+                    if next > 1 {
+                        return Err(format::error_at(
+                            lit,
+                            placeholder.arg.range.clone(),
+                            "`.*` takes the precision from an argument of its own, but the \
+                             field is the only positional argument; write the precision out \
+                             or name a sibling field, as in `{:.prec$}`",
+                        ));
+                    }
+                }
+            }
+        }
+        match placeholder.arg.kind {
+            ArgumentKind::Next => {
+                printed = true;
+                next += 1;
+                if next > 1 {
+                    return Err(format::error_at(
+                        lit,
+                        placeholder.arg.range.start - 1..placeholder.arg.range.end + 1,
+                        "format string has more placeholders than arguments, the field is the \
+                         only positional argument; use `{0}` to print it more than once",
+                    ));
+                }
+            }
+            ArgumentKind::Index(0) => printed = true,
+            _ => args.push(&placeholder.arg),
+        }
+        for arg in args {
+            let msg = match &arg.kind {
+                ArgumentKind::Name(_) => continue,
+                ArgumentKind::Index(0) | ArgumentKind::Next => {
+                    "the field cannot set its own width or precision, name a sibling field \
+                     as in `{:>width$}`"
+                        .to_owned()
+                }
+                ArgumentKind::Index(index) => format!(
+                    "invalid reference to positional argument {}, the field is the only \
+                     positional argument; use `0` to refer to it or a name for another field",
+                    index,
+                ),
+            };
+            return Err(format::error_at(lit, arg.range.clone(), msg));
+        }
+        if spec.trait_name().is_none() {
+            return Err(format::error_at(
+                lit,
+                spec.ty_range.clone(),
+                format!(
+                    "unknown format trait `{}`, expected nothing for Display or one of \
+                     `?`, `x?`, `X?`, `x`, `X`, `o`, `b`, `e`, `E`, `p`",
+                    spec.ty,
+                ),
+            ));
+        }
+    }
+    if !printed {
+        return Err(Error::new_spanned(
+            lit,
+            "format string does not print the field, add a `{}` placeholder",
+        ));
+    }
+    Ok(template)
+}
@@ -0,0 +1,210 @@
+//! Inference of the where-clause on the generated impl.
+//!
+//! Rather than requiring `FieldType: Debug` for every field, which breaks on
+//! recursive types and leaks private types into public impls, bounds are put
+//! on the type parameters themselves and on associated types projected out of
+//! them, wherever in the field type they appear. Since a bound never names the
+//! type being derived or any of its neighbours, mutually recursive types need
+//! no special treatment.
+//!
+//! Only type parameters are bounded. Lifetimes and const parameters pass
+//! through to the impl as they are, including a const parameter given as a
+//! generic argument, as in `Ring<T, N>`, which parses like a type.
+//!
+//! Some parts of a field type are known to be Debug no matter what they
+//! contain and are not searched: `PhantomData<...>` and function pointers.
+//!
+//! Handwritten `debug(bound = "...")` attributes take the place of inference:
+//! on the type for every field, or on a single field for just that field.
+
+use crate::ast::Input;
+use crate::attr::Print;
+use crate::format::ArgumentKind;
+use proc_macro2::{Span, TokenStream};
+use quote::{quote, ToTokens};
+use std::collections::BTreeSet;
+use syn::spanned::Spanned;
+use syn::visit::{self, Visit};
+use syn::{
+    parse_quote, parse_quote_spanned, Generics, Ident, Type, TypeBareFn, TypePath, WhereClause,
+    WherePredicate,
+};
+
+/// Builds the where-clause for the `Debug` impl of the input type.
+pub fn debug_where_clause(input: &Input) -> WhereClause {
+    debug_bounds(input).into_where_clause()
+}
+
+/// Builds the where-clause for the `DebugDiff` impl of the input type. A
+/// field marked `debug(diff)` is compared with its own `DebugDiff` impl, so
+/// its whole type is bounded, under the span of the key.
+pub fn diff_where_clause(input: &Input) -> WhereClause {
+    let mut bounds = debug_bounds(input);
+    if input.attrs.bound.is_none() {
+        for field in input.fields() {
+            if let (Some(diff), None) = (&field.attrs.diff, &field.attrs.bound) {
+                let ty = field.ty;
+                bounds.extend([parse_quote_spanned!(diff.span()=> #ty: ::derive_debug::DebugDiff)]);
+            }
+        }
+    }
+    bounds.into_where_clause()
+}
+
+fn debug_bounds<'a>(input: &Input<'a>) -> Bounds<'a> {
+    let debug = quote!(::core::fmt::Debug);
+    let mut bounds = Bounds::new(input.generics);
+
+    let infer = match &input.attrs.bound {
+        Some(bound) => {
+            bounds.extend(bound.iter().cloned());
+            false
+        }
+        None => true,
+    };
+
+    for variant in input.variants() {
+        for field in &variant.fields {
+            if let Some(bound) = &field.attrs.bound {
+                bounds.extend(bound.iter().cloned());
+                continue;
+            }
+            if !infer {
+                continue;
+            }
+            if let Print::Format(format) = &field.attrs.print {
+                // Each field a format string prints, whether this one or a
+                // sibling, needs the trait its placeholder asks for.
+                for placeholder in format.template.placeholders() {
+                    let ty = match &placeholder.arg.kind {
+                        ArgumentKind::Name(name) => {
+                            variant.fields[variant.field_named(name).unwrap()].ty
+                        }
+                        ArgumentKind::Next | ArgumentKind::Index(_)
+                            if field.attrs.expr.is_none() =>
+                        {
+                            field.ty
+                        }
+                        ArgumentKind::Next | ArgumentKind::Index(_) => continue,
+                    };
+                    if let Some(trait_name) = placeholder.spec.trait_name() {
+                        bounds.infer_fmt(ty, trait_name);
+                    }
+                }
+            } else if field.attrs.print.uses_debug() && field.attrs.expr.is_none() {
+                bounds.infer(field.ty, &debug);
+            }
+        }
+    }
+
+    bounds
+}
+
+/// The where-clause of an impl under construction, starting out as the
+/// input's own where-clause.
+pub struct Bounds<'a> {
+    where_clause: WhereClause,
+    params: BTreeSet<&'a Ident>,
+    seen: BTreeSet<String>,
+}
+
+impl<'a> Bounds<'a> {
+    pub fn new(generics: &'a Generics) -> Self {
+        Bounds {
+            where_clause: generics.clone().make_where_clause().clone(),
+            params: generics.type_params().map(|param| &param.ident).collect(),
+            seen: BTreeSet::new(),
+        }
+    }
+
+    pub fn extend(&mut self, predicates: impl IntoIterator<Item = WherePredicate>) {
+        self.where_clause.predicates.extend(predicates);
+    }
+
+    /// Adds the bounds needed for a value of type `ty` to implement `trait_`.
+    pub fn infer(&mut self, ty: &Type, trait_: &TokenStream) {
+        let mut bounded = Bounded {
+            params: &self.params,
+            types: Vec::new(),
+        };
+        bounded.visit_type(ty);
+        for ty in bounded.types {
+            let predicate: WherePredicate = parse_quote!(#ty: #trait_);
+            if self.seen.insert(predicate.to_token_stream().to_string()) {
+                self.where_clause.predicates.push(predicate);
+            }
+        }
+    }
+
+    /// Adds the bounds needed for a value of type `ty` to implement the
+    /// `core::fmt` trait called `trait_name`.
+    pub fn infer_fmt(&mut self, ty: &Type, trait_name: &str) {
+        let trait_name = Ident::new(trait_name, Span::call_site());
+        self.infer(ty, &quote!(::core::fmt::#trait_name));
+    }
+
+    pub fn into_where_clause(self) -> WhereClause {
+        self.where_clause
+    }
+}
+
+fn is_phantom_data(ty: &TypePath) -> bool {
+    ty.qself.is_none()
+        && ty
+            .path
+            .segments
+            .last()
+            .is_some_and(|segment| segment.ident == "PhantomData")
+}
+
+/// Finds every type parameter `T` and every associated type projected out of
+/// one, spelled `T::Assoc` or `<T as Trait>::Assoc`, mentioned in a field
+/// type.
+struct Bounded<'a> {
+    params: &'a BTreeSet<&'a Ident>,
+    types: Vec<TypePath>,
+}
+
+impl<'ast> Visit<'ast> for Bounded<'_> {
+    fn visit_type_path(&mut self, ty: &'ast TypePath) {
+        if is_phantom_data(ty) {
+            return;
+        }
+        let is_param = match &ty.qself {
+            Some(qself) => mentions_param(&qself.ty, self.params),
+            None => {
+                ty.path.leading_colon.is_none() && self.params.contains(&ty.path.segments[0].ident)
+            }
+        };
+        if is_param {
+            self.types.push(ty.clone());
+            return;
+        }
+        visit::visit_type_path(self, ty);
+    }
+
+    fn visit_type_bare_fn(&mut self, _ty: &'ast TypeBareFn) {}
+}
+
+fn mentions_param(ty: &Type, params: &BTreeSet<&Ident>) -> bool {
+    struct Mentions<'a> {
+        params: &'a BTreeSet<&'a Ident>,
+        found: bool,
+    }
+
+    impl<'ast> Visit<'ast> for Mentions<'_> {
+        fn visit_type_path(&mut self, ty: &'ast TypePath) {
+            if ty.qself.is_none() && self.params.contains(&ty.path.segments[0].ident) {
+                self.found = true;
+            }
+            visit::visit_type_path(self, ty);
+        }
+    }
+
+    let mut mentions = Mentions {
+        params,
+        found: false,
+    };
+    mentions.visit_type(ty);
+    mentions.found
+}
@@ -0,0 +1,228 @@
+//! `derive(DebugDiff)`, listing the fields in which two values differ.
+//!
+//! Fields printed as they are, without a format attribute or `expr`, are
+//! compared through `::derive_debug::__private::Pair`, which recurses into
+//! types that implement `DebugDiff` and compares the `Debug` output of the
+//! rest. That choice is made where the impl is compiled, so a field whose type
+//! is a type parameter only recurses if it is marked `debug(diff)`, which
+//! calls `DebugDiff` directly and bounds the field type by it. Every other
+//! field and extra entry is compared by what `CustomDebug` would print for it:
+//! a separate impl of `::derive_debug::__private::DiffLeaves` visits them in
+//! order, and the output for the two values is compared pairwise.
+
+use crate::ast::{Binding, Body, Input, Variant};
+use crate::attr::{Extra, Print};
+use crate::expand::{bind_self, pattern, value};
+use crate::{bound, helpers};
+use proc_macro2::TokenStream;
+use quote::{format_ident, quote};
+use syn::{DeriveInput, Error, Result};
+
+pub fn derive(input: &DeriveInput) -> Result<TokenStream> {
+    let input = Input::from_syn(input)?;
+    let ident = input.ident;
+    let (impl_generics, ty_generics, _) = input.generics.split_for_impl();
+    let where_clause = bound::diff_where_clause(&input);
+    let helpers = helpers::needed_by(&input);
+
+    let (compare, leaves) = match &input.body {
+        Body::Struct(variant) => {
+            let bind = bind_pair(variant);
+            let compare = compare(variant, &input.attrs.extras);
+            let bind_self = bind_self(variant);
+            let visits = visits(variant, &input.attrs.extras);
+            (quote!(#bind #compare), quote!(#bind_self #visits))
+        }
+        Body::Enum(variants) if variants.is_empty() => {
+            (quote!(match *self {}), quote!(match *self {}))
+        }
+        Body::Enum(variants) => {
+            let arms = variants.iter().map(|variant| {
+                let ident = variant.ident;
+                let left = pair_pattern(variant, "__self_");
+                let right = pair_pattern(variant, "__other_");
+                let compare = compare(variant, &[]);
+                quote!((Self::#ident #left, Self::#ident #right) => { #compare })
+            });
+            let names = variants.iter().map(|variant| {
+                let ident = variant.ident;
+                let name = &variant.name;
+                quote!(Self::#ident { .. } => #name,)
+            });
+            let names = quote!(#(#names)*);
+            let compare = quote! {
+                match (self, other) {
+                    #(#arms)*
+                    #[allow(unreachable_patterns)]
+                    _ => __diff.variant(
+                        match self { #names },
+                        match other { #names },
+                    ),
+                }
+            };
+            let arms = variants.iter().map(|variant| {
+                let ident = variant.ident;
+                let pattern = pattern(variant);
+                let visits = visits(variant, &[]);
+                quote!(Self::#ident #pattern => { #visits })
+            });
+            (compare, quote!(match self { #(#arms)* }))
+        }
+        Body::Union(_) => {
+            return Err(Error::new_spanned(
+                ident,
+                "DebugDiff does not support unions, which do not say which field to compare",
+            ));
+        }
+    };
+
+    // An empty enum has no values to compare, so all the setup would be
+    // unreachable.
+    let compare = match &input.body {
+        Body::Enum(variants) if variants.is_empty() => compare,
+        _ => quote! {
+            #[allow(unused_imports)]
+            use ::derive_debug::__private::{DiffLeaf as _, DiffNested as _};
+            let mut __diff = ::derive_debug::__private::Diff::default();
+            let mut __left = ::derive_debug::__private::Leaves::default();
+            let mut __right = ::derive_debug::__private::Leaves::default();
+            ::derive_debug::__private::DiffLeaves::__debug_diff_leaves(self, &mut __left);
+            ::derive_debug::__private::DiffLeaves::__debug_diff_leaves(other, &mut __right);
+            #[allow(unused_mut, unused_variables)]
+            let mut __leaves = ::core::iter::Iterator::zip(__left.0.iter(), __right.0.iter());
+            #compare
+            __diff.finish()
+        },
+    };
+
+    Ok(quote! {
+        impl #impl_generics ::derive_debug::DebugDiff for #ident #ty_generics #where_clause {
+            fn debug_diff(
+                &self,
+                other: &Self,
+            ) -> ::core::option::Option<::derive_debug::__private::String> {
+                #compare
+            }
+        }
+
+        impl #impl_generics ::derive_debug::__private::DiffLeaves for #ident #ty_generics #where_clause {
+            fn __debug_diff_leaves(&self, __visitor: &mut dyn ::derive_debug::FieldVisitor) {
+                #helpers
+                #leaves
+            }
+        }
+    })
+}
+
+/// Whether the field is compared through `Pair`, rather than by its formatted
+/// output.
+fn is_pair(variant: &Variant, i: usize) -> bool {
+    let attrs = &variant.fields[i].attrs;
+    attrs.expr.is_none() && matches!(attrs.print, Print::Debug | Print::Flatten)
+}
+
+/// A pattern for the variant's fields, less the path, which binds the `i`th
+/// field by reference to `{prefix}{i}` if it is compared through `Pair`.
+fn pair_pattern(variant: &Variant, prefix: &str) -> TokenStream {
+    let patterns = (0..variant.fields.len()).map(|i| {
+        if is_pair(variant, i) {
+            let binding = format_ident!("{}{}", prefix, i);
+            quote!(#binding)
+        } else {
+            quote!(_)
+        }
+    });
+    let members = variant.fields.iter().map(|field| &field.member);
+    quote!({ #(#members: #patterns,)* .. })
+}
+
+/// Statements binding the fields of `self` and `other`, structs, the same way
+/// that `pair_pattern` does.
+fn bind_pair(variant: &Variant) -> TokenStream {
+    if variant.binding == Binding::Ref {
+        let left = pair_pattern(variant, "__self_");
+        let right = pair_pattern(variant, "__other_");
+        return quote! {
+            let Self #left = self;
+            let Self #right = other;
+        };
+    }
+    let fields = (0..variant.fields.len())
+        .filter(|&i| is_pair(variant, i))
+        .map(|i| {
+            let left = format_ident!("__self_{}", i);
+            let right = format_ident!("__other_{}", i);
+            let member = &variant.fields[i].member;
+            quote! {
+                let #left = &{ self.#member };
+                let #right = &{ other.#member };
+            }
+        });
+    quote!(#(#fields)*)
+}
+
+/// Adds the fields of the variant that differ to `__diff`, once they are
+/// bound for both values.
+fn compare(variant: &Variant, extras: &[Extra]) -> TokenStream {
+    let fields = variant.order.iter().map(|&i| {
+        let field = &variant.fields[i];
+        let name = match field.attrs.print {
+            Print::Skip => return TokenStream::new(),
+            Print::Flatten => "",
+            _ => &field.name,
+        };
+        if field.attrs.diff.is_some() {
+            let left = format_ident!("__self_{}", i);
+            let right = format_ident!("__other_{}", i);
+            quote! {
+                __diff.nested(#name, ::derive_debug::DebugDiff::debug_diff(#left, #right));
+            }
+        } else if is_pair(variant, i) {
+            let left = format_ident!("__self_{}", i);
+            let right = format_ident!("__other_{}", i);
+            quote! {
+                (&::derive_debug::__private::Pair(#left, #right)).diff(#name, &mut __diff);
+            }
+        } else {
+            leaf(name)
+        }
+    });
+    let extras = extras.iter().map(|extra| leaf(&extra.name.value()));
+    quote! {
+        #(#fields)*
+        #(#extras)*
+    }
+}
+
+fn leaf(name: &str) -> TokenStream {
+    quote! {
+        if let ::core::option::Option::Some((__left, __right)) = __leaves.next() {
+            __diff.strings(#name, __left, __right);
+        }
+    }
+}
+
+/// Visits the fields of the variant that are compared by their formatted
+/// output, in the order that `compare` expects them, once its fields are
+/// bound. `debug(skip_if)` does not apply, so that the two values visit the
+/// same fields.
+fn visits(variant: &Variant, extras: &[Extra]) -> TokenStream {
+    let fields = variant
+        .order
+        .iter()
+        .filter(|&&i| !is_pair(variant, i) && !matches!(variant.fields[i].attrs.print, Print::Skip))
+        .map(|&i| {
+            let name = &variant.fields[i].name;
+            let value = value(variant, i);
+            quote!(__visitor.field(#name, #value);)
+        });
+    let extras = extras.iter().map(|extra| {
+        let name = &extra.name;
+        let expr = &extra.expr;
+        quote!(__visitor.field(#name, &(#expr));)
+    });
+    quote! {
+        #(#fields)*
+        #(#extras)*
+    }
+}
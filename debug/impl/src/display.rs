@@ -0,0 +1,192 @@
+//! `derive(CustomDisplay)`, implementing `Display` from a template given on the
+//! struct or on each enum variant, as in `#[display("{executable} {args:?}")]`.
+//!
+//! Placeholders name fields, or index them on tuple structs and variants. The
+//! template is checked while the macro expands and then rewritten to refer to
+//! the bindings of the generated match arm, so each field is only bounded by
+//! the formatting traits its placeholders actually use.
+
+use crate::ast::{Binding, Body, Input, Style, Variant};
+use crate::bound::Bounds;
+use crate::format::{self, Argument, ArgumentKind, Count};
+use proc_macro2::{Span, TokenStream};
+use quote::{format_ident, quote};
+use std::collections::BTreeSet;
+use syn::{Attribute, DeriveInput, Error, LitStr, Result};
+
+pub fn derive(input: &DeriveInput) -> Result<TokenStream> {
+    let enum_template = template(&input.attrs)?;
+    let input = Input::from_syn(input)?;
+    let ident = input.ident;
+    let (impl_generics, ty_generics, _) = input.generics.split_for_impl();
+    let mut bounds = Bounds::new(input.generics);
+
+    let body = match &input.body {
+        Body::Struct(variant) => {
+            let arm = arm(variant, quote!(Self), &mut bounds)?;
+            quote!(match self { #arm })
+        }
+        Body::Enum(_) if enum_template.is_some() => {
+            return Err(Error::new_spanned(
+                enum_template,
+                "an enum is displayed per variant, put `#[display(\"...\")]` on each variant",
+            ))
+        }
+        Body::Union(_) => {
+            return Err(Error::new(
+                Span::call_site(),
+                "CustomDisplay does not support unions",
+            ))
+        }
+        Body::Enum(variants) if variants.is_empty() => quote!(match *self {}),
+        Body::Enum(variants) => {
+            let mut errors = None::<Error>;
+            let mut arms = Vec::new();
+            for variant in variants {
+                let ident = variant.ident;
+                match arm(variant, quote!(Self::#ident), &mut bounds) {
+                    Ok(arm) => arms.push(arm),
+                    Err(err) => match &mut errors {
+                        Some(errors) => errors.combine(err),
+                        None => errors = Some(err),
+                    },
+                }
+            }
+            if let Some(errors) = errors {
+                return Err(errors);
+            }
+            quote!(match self { #(#arms)* })
+        }
+    };
+
+    let where_clause = bounds.into_where_clause();
+    Ok(quote! {
+        impl #impl_generics ::core::fmt::Display for #ident #ty_generics #where_clause {
+            fn fmt(&self, f: &mut ::core::fmt::Formatter<'_>) -> ::core::fmt::Result {
+                #body
+            }
+        }
+    })
+}
+
+fn template(attrs: &[Attribute]) -> Result<Option<LitStr>> {
+    let mut template = None;
+    for attr in attrs {
+        if attr.path().is_ident("display") {
+            if template.is_some() {
+                return Err(Error::new_spanned(attr, "duplicate `display` attribute"));
+            }
+            template = Some(attr.parse_args()?);
+        }
+    }
+    Ok(template)
+}
+
+fn arm(variant: &Variant, path: TokenStream, bounds: &mut Bounds) -> Result<TokenStream> {
+    let lit = match template(variant.original_attrs)? {
+        Some(lit) => lit,
+        None => {
+            return Err(Error::new_spanned(
+                variant.ident,
+                "missing `#[display(\"...\")]` template",
+            ))
+        }
+    };
+    let template = format::parse(&lit.value()).map_err(|err| format::error(&lit, err))?;
+
+    let mut errors = Vec::new();
+    let mut values = BTreeSet::new();
+    let mut counts = BTreeSet::new();
+    for placeholder in template.placeholders() {
+        let spec = &placeholder.spec;
+        let trait_name = spec.trait_name();
+        if trait_name.is_none() {
+            let msg = format!("unknown format trait `{}`", spec.ty);
+            errors.push(format::error_at(&lit, spec.ty_range.clone(), msg));
+        }
+        match field_index(variant, &placeholder.arg) {
+            Some(index) => {
+                values.insert(index);
+                if let Some(trait_name) = trait_name {
+                    bounds.infer_fmt(variant.fields[index].ty, trait_name);
+                }
+            }
+            None => errors.push(variant.unknown_field(&placeholder.arg, &lit)),
+        }
+        for count in [&spec.width, &spec.precision].into_iter().flatten() {
+            match count {
+                Count::Is(_) => {}
+                Count::Param(arg) => match field_index(variant, arg) {
+                    Some(index) => {
+                        counts.insert(index);
+                    }
+                    None => errors.push(variant.unknown_field(arg, &lit)),
+                },
+                Count::Star => errors.push(format::error_at(
+                    &lit,
+                    placeholder.arg.range.clone(),
+                    "`.*` is not supported in templates, name a field as in `.precision$`",
+                )),
+            }
+        }
+    }
+    if let Some(mut error) = errors.pop() {
+        for err in errors {
+            error.combine(err);
+        }
+        return Err(error);
+    }
+
+    let rendered = template.render(|arg, is_count| {
+        let i = field_index(variant, arg).unwrap();
+        if is_count {
+            format!("__count_{}", i)
+        } else {
+            format!("__self_{}", i)
+        }
+    });
+    let rendered = LitStr::new(&rendered, lit.span());
+
+    let bindings = (0..variant.fields.len())
+        .map(|i| format_ident!("__self_{}", i))
+        .collect::<Vec<_>>();
+    let value_args = values.iter().map(|&i| &bindings[i]);
+    let count_args = counts.iter().map(|&i| {
+        let binding = &bindings[i];
+        let count = format_ident!("__count_{}", i);
+        quote!(#count = *#binding)
+    });
+    let write = quote! {
+        ::core::write!(f, #rendered #(, #value_args = #value_args)* #(, #count_args)*)
+    };
+
+    if variant.binding == Binding::Copy {
+        // Fields of a packed struct may be unaligned, so the ones the template
+        // uses are copied out instead.
+        let copies = values.union(&counts).map(|&i| {
+            let binding = &bindings[i];
+            let member = &variant.fields[i].member;
+            quote!(let #binding = &{ self.#member };)
+        });
+        return Ok(quote!(#path { .. } => { #(#copies)* #write }));
+    }
+
+    Ok(match variant.style {
+        Style::Unit => quote!(#path => #write,),
+        Style::Tuple => quote!(#path(#(#bindings),*) => #write,),
+        Style::Named => {
+            let members = variant.fields.iter().map(|field| &field.member);
+            quote!(#path { #(#members: #bindings),* } => #write,)
+        }
+    })
+}
+
+fn field_index(variant: &Variant, arg: &Argument) -> Option<usize> {
+    match &arg.kind {
+        ArgumentKind::Name(name) => variant.field_named(name),
+        ArgumentKind::Index(index) if variant.style == Style::Tuple => {
+            Some(*index).filter(|index| *index < variant.fields.len())
+        }
+        ArgumentKind::Index(_) | ArgumentKind::Next => None,
+    }
+}
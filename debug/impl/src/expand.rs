@@ -0,0 +1,428 @@
+use crate::ast::{Binding, Body, Input, Style, Variant};
+use crate::attr::{Discriminant, Extra, FieldFormat, Print, Radix};
+use crate::format::{ArgumentKind, Count};
+use crate::{bound, helpers};
+use proc_macro2::TokenStream;
+use quote::{format_ident, quote, quote_spanned, ToTokens};
+use std::collections::BTreeSet;
+use syn::{DeriveInput, LitStr, Result};
+
+pub fn derive(input: &DeriveInput) -> Result<TokenStream> {
+    let input = Input::from_syn(input)?;
+    let ident = input.ident;
+    let (impl_generics, ty_generics, _) = input.generics.split_for_impl();
+    let where_clause = bound::debug_where_clause(&input);
+    let helpers = helpers::needed_by(&input);
+
+    let mut fields_impl = None;
+    let (helpers, body) = match &input.body {
+        Body::Struct(variant) if input.attrs.transparent.is_some() => {
+            let bind = bind_self(variant);
+            let value = value(variant, 0);
+            let body = quote! {
+                #bind
+                ::core::fmt::Debug::fmt(#value, f)
+            };
+            (Some(helpers), body)
+        }
+        Body::Struct(variant) if variant.style == Style::Named => {
+            // The entries are added by a separate trait method so that a
+            // struct holding this one can inline them with `debug(flatten)`.
+            let bind = bind_self(variant);
+            let Entries {
+                statements,
+                skipped,
+            } = entries(variant, &input.attrs.extras, variant.style);
+            let skipped = skipped.unwrap_or_else(|| quote!(false));
+            fields_impl = Some(quote! {
+                impl #impl_generics ::derive_debug::__private::DebugFields
+                    for #ident #ty_generics #where_clause
+                {
+                    fn __debug_fields(
+                        &self,
+                        __builder: &mut ::core::fmt::DebugStruct<'_, '_>,
+                    ) -> ::core::primitive::bool {
+                        #helpers
+                        #bind
+                        #statements
+                        #skipped
+                    }
+                }
+            });
+            let name = &variant.name;
+            let body = quote! {
+                let mut __builder = f.debug_struct(#name);
+                if ::derive_debug::__private::DebugFields::__debug_fields(self, &mut __builder) {
+                    __builder.finish_non_exhaustive()
+                } else {
+                    __builder.finish()
+                }
+            };
+            (None, body)
+        }
+        Body::Struct(variant) => {
+            let bind = bind_self(variant);
+            let print = print(variant);
+            (Some(helpers), quote!(#bind #print))
+        }
+        Body::Enum(variants) if variants.is_empty() => (None, quote!(match *self {})),
+        Body::Enum(variants) => {
+            let arms = variants.iter().map(|variant| {
+                let ident = variant.ident;
+                let pattern = pattern(variant);
+                let print = match &input.attrs.discriminant {
+                    Some(discriminant) => {
+                        let prefix = discriminant_prefix(variant, discriminant);
+                        // The prefix already names the variant, and
+                        // tuple fields are named by their index, so that
+                        // the fields follow it as `{ 0: .. }` rather than as
+                        // a nameless tuple.
+                        let style = match variant.style {
+                            Style::Unit => Style::Unit,
+                            Style::Tuple | Style::Named => Style::Named,
+                        };
+                        let print = print_as(variant, "", style);
+                        quote!(#prefix #print)
+                    }
+                    None => print(variant),
+                };
+                quote!(Self::#ident #pattern => { #print })
+            });
+            (Some(helpers), quote!(match self { #(#arms)* }))
+        }
+        Body::Union(variant) if variant.fields.is_empty() => {
+            let name = &variant.name;
+            let body = match &input.attrs.unsafe_raw_bytes {
+                Some(_) => {
+                    let bytes = union_bytes();
+                    quote!(f.debug_tuple(#name).field(#bytes).finish())
+                }
+                None => quote!(f.debug_struct(#name).finish_non_exhaustive()),
+            };
+            (None, body)
+        }
+        Body::Union(variant) => {
+            let bind = bind_self(variant);
+            let print = print(variant);
+            (Some(helpers), quote!(#bind #print))
+        }
+    };
+
+    // Formatting `self` again with a fresh `{:?}` drops the `#` flag.
+    let compact = input.attrs.compact.as_ref().map(|_| {
+        quote! {
+            if f.alternate() {
+                return ::core::write!(f, "{:?}", self);
+            }
+        }
+    });
+
+    Ok(quote! {
+        impl #impl_generics ::core::fmt::Debug for #ident #ty_generics #where_clause {
+            fn fmt(&self, f: &mut ::core::fmt::Formatter<'_>) -> ::core::fmt::Result {
+                #compact
+                #helpers
+                #body
+            }
+        }
+
+        #fields_impl
+    })
+}
+
+/// The statements that add a variant's printed fields and computed entries
+/// to `__builder`.
+struct Entries {
+    statements: TokenStream,
+    /// Whether any entry was left out at runtime, which is then marked with a
+    /// trailing `..`, if the statements can leave entries out.
+    skipped: Option<TokenStream>,
+}
+
+/// The fields are added with names if `style` is `Named`, by their index for
+/// tuple fields, and without otherwise.
+fn entries(variant: &Variant, extras: &[Extra], style: Style) -> Entries {
+    let printed = variant
+        .order
+        .iter()
+        .map(|&i| (i, &variant.fields[i]))
+        .filter(|(_, field)| !matches!(field.attrs.print, Print::Skip))
+        .map(|(i, field)| (i, field, format_ident!("__self_{}", i)))
+        .collect::<Vec<_>>();
+    let entries = printed.iter().map(|(i, field, binding)| {
+        let entry = match (&field.attrs.print, style) {
+            (Print::Flatten, _) => {
+                let value = value(variant, *i);
+                quote! {
+                    if (#value).__debug_fields(__builder) {
+                        __skipped = true;
+                    }
+                }
+            }
+            (_, Style::Named) => {
+                let name = &field.name;
+                let value = value(variant, *i);
+                quote!(__builder.field(#name, #value);)
+            }
+            (_, Style::Tuple | Style::Unit) => {
+                let value = value(variant, *i);
+                quote!(__builder.field(#value);)
+            }
+        };
+        match &field.attrs.skip_if {
+            Some(predicate) => quote! {
+                if #predicate(#binding) {
+                    __skipped = true;
+                } else {
+                    #entry
+                }
+            },
+            None => entry,
+        }
+    });
+    let extras = extras.iter().map(|extra| {
+        let name = &extra.name;
+        let expr = &extra.expr;
+        quote!(__builder.field(#name, &(#expr));)
+    });
+
+    let may_skip = printed.iter().any(|(_, field, _)| {
+        field.attrs.skip_if.is_some() || matches!(field.attrs.print, Print::Flatten)
+    });
+    let declare = may_skip.then(|| quote!(let mut __skipped = false;));
+    // Called as a method, so that flattened fields may be behind a `Box`.
+    let flatten = printed
+        .iter()
+        .any(|(_, field, _)| matches!(field.attrs.print, Print::Flatten))
+        .then(|| {
+            quote!(
+                use ::derive_debug::__private::DebugFields as _;
+            )
+        });
+    Entries {
+        statements: quote! {
+            #flatten
+            #declare
+            #(#entries)*
+            #(#extras)*
+        },
+        skipped: may_skip.then(|| quote!(__skipped)),
+    }
+}
+
+/// A pattern for the variant's fields, less the path, which binds the `i`th
+/// field by reference to `__self_{i}` if it is printed or is referred to by a
+/// format string.
+pub fn pattern(variant: &Variant) -> TokenStream {
+    let bound = bound_fields(variant);
+    let patterns = (0..variant.fields.len()).map(|i| {
+        if bound.contains(&i) {
+            let binding = format_ident!("__self_{}", i);
+            quote!(#binding)
+        } else {
+            quote!(_)
+        }
+    });
+    match variant.style {
+        Style::Named => {
+            let members = variant.fields.iter().map(|field| &field.member);
+            quote!({ #(#members: #patterns),* })
+        }
+        Style::Tuple => quote!((#(#patterns),*)),
+        Style::Unit => TokenStream::new(),
+    }
+}
+
+/// Statements binding the fields of `self`, a struct or union, the same way
+/// that `pattern` does.
+pub fn bind_self(variant: &Variant) -> TokenStream {
+    if variant.binding == Binding::Ref {
+        let pattern = pattern(variant);
+        return quote!(let Self #pattern = self;);
+    }
+    let fields = bound_fields(variant).into_iter().map(|i| {
+        let binding = format_ident!("__self_{}", i);
+        let member = &variant.fields[i].member;
+        if variant.binding == Binding::Copy {
+            quote!(let #binding = &{ self.#member };)
+        } else {
+            quote!(let #binding = unsafe { &self.#member };)
+        }
+    });
+    quote!(#(#fields)*)
+}
+
+/// The bytes of `self`, a union, formatted as hex, for `debug(unsafe_raw_bytes)`.
+/// Reading them is only sound because the attribute promises that all of them
+/// are always initialized.
+pub fn union_bytes() -> TokenStream {
+    quote! {
+        &::core::format_args!("{:02x?}", unsafe {
+            ::core::slice::from_raw_parts(
+                self as *const Self as *const ::core::primitive::u8,
+                ::core::mem::size_of::<Self>(),
+            )
+        })
+    }
+}
+
+/// The indices of the fields that are printed or referred to by a format
+/// string.
+fn bound_fields(variant: &Variant) -> BTreeSet<usize> {
+    let mut bound = BTreeSet::new();
+    for (i, field) in variant.fields.iter().enumerate() {
+        match &field.attrs.print {
+            Print::Skip => continue,
+            Print::Format(format) => bound.extend(siblings(variant, format).map(|(j, _)| j)),
+            _ => {}
+        }
+        bound.insert(i);
+    }
+    bound
+}
+
+/// Writes the variant's name along with its discriminant, as `Name(1)` or
+/// `Name = 0x01`, which the variant's fields then follow as a struct's would.
+fn discriminant_prefix(variant: &Variant, discriminant: &Discriminant) -> TokenStream {
+    let name = &variant.name;
+    let ident = variant.ident;
+    // An integer representation puts the discriminant first, even in variants
+    // with fields, and fieldless enums are checked to be castable instead.
+    let (value, width) = match &discriminant.repr {
+        Some(repr) => (
+            quote!(unsafe { *(self as *const Self as *const ::core::primitive::#repr) }),
+            quote!(::core::mem::size_of::<::core::primitive::#repr>() * 2 + 2),
+        ),
+        None => (quote!(Self::#ident as ::core::primitive::isize), quote!(0)),
+    };
+    if discriminant.hex {
+        quote!(::core::write!(f, "{} = {:#0width$x}", #name, #value, width = #width)?;)
+    } else {
+        quote!(::core::write!(f, "{}({})", #name, #value)?;)
+    }
+}
+
+/// Prints the variant the way the standard library's derive would, once its
+/// fields are bound.
+fn print(variant: &Variant) -> TokenStream {
+    print_as(variant, &variant.name, variant.style)
+}
+
+/// Prints the variant like `print`, under the given name and as a variant of
+/// the given style.
+fn print_as(variant: &Variant, name: &str, style: Style) -> TokenStream {
+    let Entries {
+        statements,
+        skipped,
+    } = entries(variant, &[], style);
+    let builder = match style {
+        Style::Unit => return quote!(f.write_str(#name)),
+        Style::Tuple => quote!(f.debug_tuple(#name)),
+        Style::Named => quote!(f.debug_struct(#name)),
+    };
+    let finish = match skipped {
+        Some(skipped) => quote! {
+            if #skipped {
+                __builder.finish_non_exhaustive()
+            } else {
+                __builder.finish()
+            }
+        },
+        None => quote!(__builder.finish()),
+    };
+    quote! {
+        let mut __builder = #builder;
+        let __builder = &mut __builder;
+        #statements
+        #finish
+    }
+}
+
+/// The value passed to the builder for the `i`th field of the variant, which
+/// is bound to `__self_{i}`.
+pub fn value(variant: &Variant, i: usize) -> TokenStream {
+    let field = &variant.fields[i];
+    let binding = format_ident!("__self_{}", i);
+    let binding = match &field.attrs.expr {
+        Some(expr) => quote!(&(#expr)),
+        None => quote!(#binding),
+    };
+    match &field.attrs.print {
+        Print::Debug => binding,
+        Print::Format(format) => {
+            let rendered = format.template.render(|arg, is_count| match &arg.kind {
+                ArgumentKind::Name(name) => {
+                    let j = variant.field_named(name).unwrap();
+                    if is_count {
+                        format!("__count_{}", j)
+                    } else {
+                        format!("__value_{}", j)
+                    }
+                }
+                ArgumentKind::Next | ArgumentKind::Index(_) => "__value".to_owned(),
+            });
+            let rendered = LitStr::new(&rendered, format.lit.span());
+            let mut args = vec![quote!(__value = #binding)];
+            let mut seen = BTreeSet::new();
+            for (j, is_count) in siblings(variant, format) {
+                if seen.insert((j, is_count)) {
+                    let sibling = format_ident!("__self_{}", j);
+                    args.push(if is_count {
+                        let count = format_ident!("__count_{}", j);
+                        quote!(#count = *#sibling)
+                    } else {
+                        let value = format_ident!("__value_{}", j);
+                        quote!(#value = #sibling)
+                    });
+                }
+            }
+            quote!(&::core::format_args!(#rendered #(, #args)*))
+        }
+        Print::Redact(placeholder) => quote!(&::core::format_args!("{}", #placeholder)),
+        Print::With(function) => quote!(&__DebugWith(#binding, #function)),
+        Print::MaxItems(max) => quote!(&__DebugMaxItems(#binding, #max)),
+        Print::MaxLen(max) => quote!(&#binding.__debug_max_len(#max)),
+        Print::Radix(radix, span) => {
+            let radix = match radix {
+                Radix::Hex => 16u32,
+                Radix::Binary => 2u32,
+            };
+            // Spanned at the key, where an unsupported field type is then
+            // reported.
+            let value = match &field.attrs.expr {
+                Some(expr) => quote_spanned!(*span=> &(#expr)),
+                None => format_ident!("__self_{}", i, span = *span).into_token_stream(),
+            };
+            quote_spanned!(*span=> &::derive_debug::__private::DebugRadix::new(#value, #radix))
+        }
+        Print::Bytes => quote!(&#binding.__debug_bytes(false)),
+        Print::Hexdump => quote!(&#binding.__debug_bytes(true)),
+        Print::Flatten => binding,
+        Print::Skip => unreachable!(),
+    }
+}
+
+/// The sibling fields a format string refers to by name, as indices into the
+/// variant's fields, each paired with whether it is used as a width or
+/// precision.
+fn siblings<'a>(
+    variant: &'a Variant,
+    format: &'a FieldFormat,
+) -> impl Iterator<Item = (usize, bool)> + 'a {
+    format.template.placeholders().flat_map(move |placeholder| {
+        let spec = &placeholder.spec;
+        let counts = [&spec.width, &spec.precision]
+            .into_iter()
+            .flatten()
+            .filter_map(|count| match count {
+                Count::Param(arg) => Some((arg, true)),
+                Count::Is(_) | Count::Star => None,
+            });
+        std::iter::once((&placeholder.arg, false))
+            .chain(counts)
+            .filter_map(move |(arg, is_count)| match &arg.kind {
+                ArgumentKind::Name(name) => Some((variant.field_named(name)?, is_count)),
+                ArgumentKind::Next | ArgumentKind::Index(_) => None,
+            })
+    })
+}
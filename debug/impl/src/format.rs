@@ -0,0 +1,408 @@
+//! A parser for the format string syntax of `std::fmt`, so that templates can
+//! be checked while the macro expands and their arguments rewritten to refer
+//! to the bindings of the generated code.
+
+use proc_macro2::Span;
+use std::fmt::{Display, Write as _};
+use std::ops::Range;
+use syn::{Error, LitStr};
+
+pub struct FormatString {
+    pub pieces: Vec<Piece>,
+}
+
+pub enum Piece {
+    /// Text printed as is, with `{{` and `}}` already unescaped.
+    Text(String),
+    Placeholder(Placeholder),
+}
+
+pub struct Placeholder {
+    pub arg: Argument,
+    pub spec: Spec,
+}
+
+pub struct Argument {
+    pub kind: ArgumentKind,
+    /// Byte range of the argument within the string's value.
+    pub range: Range<usize>,
+}
+
+pub enum ArgumentKind {
+    /// `{}`, the next positional argument.
+    Next,
+    /// `{0}`
+    Index(usize),
+    /// `{name}`
+    Name(String),
+}
+
+#[derive(Default)]
+pub struct Spec {
+    pub fill: Option<char>,
+    pub align: Option<char>,
+    pub sign: Option<char>,
+    pub alternate: bool,
+    pub zero: bool,
+    pub width: Option<Count>,
+    pub precision: Option<Count>,
+    pub ty: String,
+    /// Byte range of the type within the string's value.
+    pub ty_range: Range<usize>,
+}
+
+pub enum Count {
+    Is(usize),
+    Param(Argument),
+    /// `.*`, taking the precision from the next positional argument.
+    Star,
+}
+
+/// A mistake in a format string, located by a byte range of its value.
+pub struct ParseError {
+    pub msg: String,
+    pub range: Range<usize>,
+}
+
+impl FormatString {
+    pub fn placeholders(&self) -> impl Iterator<Item = &Placeholder> {
+        self.pieces.iter().filter_map(|piece| match piece {
+            Piece::Text(_) => None,
+            Piece::Placeholder(placeholder) => Some(placeholder),
+        })
+    }
+
+    /// Every argument of the format string, including width and precision
+    /// parameters.
+    pub fn arguments(&self) -> impl Iterator<Item = &Argument> {
+        self.placeholders().flat_map(|placeholder| {
+            let spec = &placeholder.spec;
+            let counts = [&spec.width, &spec.precision]
+                .into_iter()
+                .flatten()
+                .filter_map(|count| match count {
+                    Count::Param(arg) => Some(arg),
+                    Count::Is(_) | Count::Star => None,
+                });
+            std::iter::once(&placeholder.arg).chain(counts)
+        })
+    }
+
+    /// Writes the format string back out with every argument replaced by the
+    /// name `rename` returns. The second argument of `rename` is true for
+    /// width and precision parameters, which must be `usize` values.
+    pub fn render(&self, mut rename: impl FnMut(&Argument, bool) -> String) -> String {
+        let mut out = String::new();
+        for piece in &self.pieces {
+            match piece {
+                Piece::Text(text) => out.push_str(&text.replace('{', "{{").replace('}', "}}")),
+                Piece::Placeholder(placeholder) => {
+                    let spec = &placeholder.spec;
+                    out.push('{');
+                    out.push_str(&rename(&placeholder.arg, false));
+                    out.push(':');
+                    if let Some(align) = spec.align {
+                        out.extend(spec.fill);
+                        out.push(align);
+                    }
+                    out.extend(spec.sign);
+                    if spec.alternate {
+                        out.push('#');
+                    }
+                    if spec.zero {
+                        out.push('0');
+                    }
+                    if let Some(width) = &spec.width {
+                        render_count(&mut out, width, &mut rename);
+                    }
+                    if let Some(precision) = &spec.precision {
+                        out.push('.');
+                        render_count(&mut out, precision, &mut rename);
+                    }
+                    out.push_str(&spec.ty);
+                    out.push('}');
+                }
+            }
+        }
+        out
+    }
+}
+
+fn render_count(
+    out: &mut String,
+    count: &Count,
+    rename: &mut impl FnMut(&Argument, bool) -> String,
+) {
+    match count {
+        Count::Is(n) => write!(out, "{}", n).unwrap(),
+        Count::Param(arg) => write!(out, "{}$", rename(arg, true)).unwrap(),
+        Count::Star => out.push('*'),
+    }
+}
+
+impl Spec {
+    /// The formatting trait this placeholder invokes, or `None` if the type
+    /// is not one that `std::fmt` knows.
+    pub fn trait_name(&self) -> Option<&'static str> {
+        Some(match self.ty.as_str() {
+            "" => "Display",
+            "?" | "x?" | "X?" => "Debug",
+            "x" => "LowerHex",
+            "X" => "UpperHex",
+            "o" => "Octal",
+            "b" => "Binary",
+            "e" => "LowerExp",
+            "E" => "UpperExp",
+            "p" => "Pointer",
+            _ => return None,
+        })
+    }
+}
+
+pub fn parse(s: &str) -> Result<FormatString, ParseError> {
+    let mut parser = Parser { s, pos: 0 };
+    let mut pieces = Vec::new();
+    let mut text = String::new();
+    while let Some(ch) = parser.peek() {
+        match ch {
+            '{' if parser.rest().starts_with("{{") => {
+                text.push('{');
+                parser.pos += 2;
+            }
+            '}' if parser.rest().starts_with("}}") => {
+                text.push('}');
+                parser.pos += 2;
+            }
+            '{' => {
+                if !text.is_empty() {
+                    pieces.push(Piece::Text(std::mem::take(&mut text)));
+                }
+                pieces.push(Piece::Placeholder(parser.placeholder()?));
+            }
+            '}' => {
+                return Err(ParseError {
+                    msg: "unmatched `}` in format string, use `}}` to print a brace".to_owned(),
+                    range: parser.pos..parser.pos + 1,
+                })
+            }
+            _ => {
+                text.push(ch);
+                parser.pos += ch.len_utf8();
+            }
+        }
+    }
+    if !text.is_empty() {
+        pieces.push(Piece::Text(text));
+    }
+    Ok(FormatString { pieces })
+}
+
+struct Parser<'a> {
+    s: &'a str,
+    pos: usize,
+}
+
+impl Parser<'_> {
+    fn rest(&self) -> &str {
+        &self.s[self.pos..]
+    }
+
+    fn peek(&self) -> Option<char> {
+        self.rest().chars().next()
+    }
+
+    fn peek2(&self) -> Option<char> {
+        self.rest().chars().nth(1)
+    }
+
+    fn bump(&mut self) -> Option<char> {
+        let ch = self.peek()?;
+        self.pos += ch.len_utf8();
+        Some(ch)
+    }
+
+    fn eat(&mut self, expected: char) -> bool {
+        let found = self.peek() == Some(expected);
+        if found {
+            self.pos += expected.len_utf8();
+        }
+        found
+    }
+
+    fn error(&self, msg: impl Into<String>, range: Range<usize>) -> ParseError {
+        ParseError {
+            msg: msg.into(),
+            range,
+        }
+    }
+
+    fn placeholder(&mut self) -> Result<Placeholder, ParseError> {
+        let open = self.pos;
+        self.bump();
+        let arg = self.argument()?;
+        let mut spec = Spec::default();
+        if self.eat(':') {
+            self.spec(&mut spec)?;
+        } else {
+            spec.ty_range = self.pos..self.pos;
+        }
+        if !self.eat('}') {
+            return Err(match self.rest().find('}') {
+                Some(len) => self.error(
+                    format!(
+                        "invalid format string, unexpected `{}`",
+                        &self.rest()[..len],
+                    ),
+                    self.pos..self.pos + len,
+                ),
+                None => self.error("unterminated placeholder, expected `}`", open..self.s.len()),
+            });
+        }
+        Ok(Placeholder { arg, spec })
+    }
+
+    fn argument(&mut self) -> Result<Argument, ParseError> {
+        let start = self.pos;
+        let len = self
+            .rest()
+            .find(|ch: char| !(ch.is_alphanumeric() || ch == '_'))
+            .unwrap_or(self.rest().len());
+        self.pos += len;
+        let text = &self.s[start..self.pos];
+        let kind = if text.is_empty() {
+            ArgumentKind::Next
+        } else if text.bytes().all(|b| b.is_ascii_digit()) {
+            match text.parse() {
+                Ok(index) => ArgumentKind::Index(index),
+                Err(_) => return Err(self.error("argument index is too large", start..self.pos)),
+            }
+        } else if text.starts_with(|ch: char| ch.is_ascii_digit()) {
+            return Err(self.error(format!("invalid argument name `{}`", text), start..self.pos));
+        } else {
+            ArgumentKind::Name(text.to_owned())
+        };
+        Ok(Argument {
+            kind,
+            range: start..self.pos,
+        })
+    }
+
+    fn spec(&mut self, spec: &mut Spec) -> Result<(), ParseError> {
+        if let Some(align @ ('<' | '^' | '>')) = self.peek2() {
+            spec.fill = self.bump();
+            spec.align = Some(align);
+            self.bump();
+        } else if let Some(align @ ('<' | '^' | '>')) = self.peek() {
+            spec.align = Some(align);
+            self.bump();
+        }
+        if let Some(sign @ ('+' | '-')) = self.peek() {
+            spec.sign = Some(sign);
+            self.bump();
+        }
+        spec.alternate = self.eat('#');
+        if self.peek() == Some('0') && self.peek2() != Some('$') {
+            spec.zero = true;
+            self.bump();
+        }
+        spec.width = self.count()?;
+        if self.eat('.') {
+            if self.eat('*') {
+                spec.precision = Some(Count::Star);
+            } else {
+                let start = self.pos;
+                spec.precision = self.count()?;
+                if spec.precision.is_none() {
+                    return Err(self.error("expected a precision after `.`", start - 1..start));
+                }
+            }
+        }
+        let start = self.pos;
+        let len = self
+            .rest()
+            .find(|ch: char| !(ch.is_alphanumeric() || ch == '_' || ch == '?'))
+            .unwrap_or(self.rest().len());
+        self.pos += len;
+        spec.ty = self.s[start..self.pos].to_owned();
+        spec.ty_range = start..self.pos;
+        Ok(())
+    }
+
+    /// Parses a width or precision: `5`, `1$` or `name$`.
+    fn count(&mut self) -> Result<Option<Count>, ParseError> {
+        let start = self.pos;
+        let len = self
+            .rest()
+            .find(|ch: char| !(ch.is_alphanumeric() || ch == '_'))
+            .unwrap_or(self.rest().len());
+        let text = &self.s[start..start + len];
+        if text.is_empty() {
+            return Ok(None);
+        }
+        if self.s[start + len..].starts_with('$') {
+            self.pos += len;
+            let arg = self.argument_from(start)?;
+            self.pos += 1;
+            return Ok(Some(Count::Param(arg)));
+        }
+        let digits = text.bytes().take_while(u8::is_ascii_digit).count();
+        if digits == 0 {
+            // Not a count; this is the type, as in `{:x}`.
+            return Ok(None);
+        }
+        self.pos += digits;
+        match text[..digits].parse() {
+            Ok(n) => Ok(Some(Count::Is(n))),
+            Err(_) => Err(self.error("width or precision is too large", start..self.pos)),
+        }
+    }
+
+    fn argument_from(&mut self, start: usize) -> Result<Argument, ParseError> {
+        let end = self.pos;
+        self.pos = start;
+        let arg = self.argument()?;
+        self.pos = end;
+        Ok(arg)
+    }
+}
+
+/// An error about a byte range of the literal's value. Only nightly compilers
+/// can point inside a literal, so the offending fragment and its column are
+/// quoted in the message as well, where they show on stable too.
+pub fn error_at(lit: &LitStr, range: Range<usize>, msg: impl Display) -> Error {
+    let value = lit.value();
+    let column = value
+        .get(..range.start)
+        .map_or(0, |before| before.chars().count())
+        + 1;
+    let msg = match value.get(range.clone()) {
+        Some(fragment) if !fragment.is_empty() => {
+            format!("{} (`{}` at column {})", msg, fragment, column)
+        }
+        _ => format!("{} (at column {})", msg, column),
+    };
+    Error::new(span_of(lit, range), msg)
+}
+
+/// The span of a byte range of the literal's value, if the compiler can point
+/// inside the literal, or else the span of the whole literal.
+fn span_of(lit: &LitStr, range: Range<usize>) -> Span {
+    let value = lit.value();
+    let repr = lit.token().to_string();
+    // Offsets into the value only line up with the source when the literal
+    // has no escapes, which is when the value appears in it verbatim.
+    let prefix = match repr.find('"') {
+        Some(quote) => quote + 1,
+        None => return lit.span(),
+    };
+    if repr.get(prefix..prefix + value.len()) != Some(value.as_str()) {
+        return lit.span();
+    }
+    lit.token()
+        .subspan(prefix + range.start..prefix + range.end)
+        .unwrap_or_else(|| lit.span())
+}
+
+pub fn error(lit: &LitStr, err: ParseError) -> Error {
+    error_at(lit, err.range, err.msg)
+}
@@ -0,0 +1,135 @@
+//! Items emitted inside the generated `fmt` function to support particular
+//! field attributes. They are local to the function body, so they cannot
+//! collide with anything in the caller's crate, and are only emitted when some
+//! field needs them. Larger ones, such as the `hex` and `bytes` presets, live
+//! in `derive_debug::__private` instead.
+
+use crate::ast::Input;
+use crate::attr::Print;
+use proc_macro2::TokenStream;
+use quote::quote;
+
+pub fn needed_by(input: &Input) -> TokenStream {
+    let mut with = false;
+    let mut max_items = false;
+    let mut max_len = false;
+    let mut bytes = false;
+    for field in input.fields() {
+        match field.attrs.print {
+            Print::With(_) => with = true,
+            Print::MaxItems(_) => max_items = true,
+            Print::MaxLen(_) => max_len = true,
+            Print::Bytes | Print::Hexdump => bytes = true,
+            _ => {}
+        }
+    }
+
+    let with = with.then(debug_with);
+    let max_items = max_items.then(debug_max_items);
+    let max_len = max_len.then(debug_max_len);
+    // Bytes are printed through a method call, so that anything that derefs
+    // to `[u8]` is accepted, which needs the trait in scope.
+    let bytes = bytes.then(|| {
+        quote!(
+            use ::derive_debug::__private::Bytes as _;
+        )
+    });
+    quote! {
+        #with
+        #max_items
+        #max_len
+        #bytes
+    }
+}
+
+/// Adapts a `debug(with = "...")` function into something that can be passed
+/// to `DebugStruct::field`.
+fn debug_with() -> TokenStream {
+    quote! {
+        struct __DebugWith<'__a, __T: ?::core::marker::Sized>(
+            &'__a __T,
+            fn(&__T, &mut ::core::fmt::Formatter<'_>) -> ::core::fmt::Result,
+        );
+
+        impl<__T: ?::core::marker::Sized> ::core::fmt::Debug for __DebugWith<'_, __T> {
+            fn fmt(&self, f: &mut ::core::fmt::Formatter<'_>) -> ::core::fmt::Result {
+                (self.1)(self.0, f)
+            }
+        }
+    }
+}
+
+/// Prints the first elements of anything iterable by reference as a list,
+/// followed by a count of the elements left out.
+fn debug_max_items() -> TokenStream {
+    quote! {
+        struct __DebugMaxItems<'__a, __T: ?::core::marker::Sized>(&'__a __T, ::core::primitive::usize);
+
+        impl<'__a, __T: ?::core::marker::Sized> ::core::fmt::Debug for __DebugMaxItems<'__a, __T>
+        where
+            &'__a __T: ::core::iter::IntoIterator,
+            <&'__a __T as ::core::iter::IntoIterator>::Item: ::core::fmt::Debug,
+        {
+            fn fmt(&self, f: &mut ::core::fmt::Formatter<'_>) -> ::core::fmt::Result {
+                let mut list = f.debug_list();
+                let mut iter = ::core::iter::IntoIterator::into_iter(self.0);
+                list.entries(::core::iter::Iterator::take(&mut iter, self.1));
+                let rest = ::core::iter::Iterator::count(iter);
+                if rest > 0 {
+                    list.entry(&::core::format_args!("... ({} more)", rest));
+                }
+                list.finish()
+            }
+        }
+    }
+}
+
+/// Prints the start of a string, or of a byte slice as a list of bytes,
+/// followed by a count of what was left out. The entry point is a method so
+/// that auto-deref finds `str` or `[u8]` behind `String`, `Vec<u8>`, `&str`,
+/// arrays, boxes and the like.
+fn debug_max_len() -> TokenStream {
+    quote! {
+        struct __DebugMaxLen<'__a, __T: ?::core::marker::Sized>(&'__a __T, ::core::primitive::usize);
+
+        trait __MaxLen {
+            fn __debug_max_len(&self, max: ::core::primitive::usize) -> __DebugMaxLen<'_, Self>;
+        }
+
+        impl __MaxLen for ::core::primitive::str {
+            fn __debug_max_len(&self, max: ::core::primitive::usize) -> __DebugMaxLen<'_, Self> {
+                __DebugMaxLen(self, max)
+            }
+        }
+
+        impl __MaxLen for [::core::primitive::u8] {
+            fn __debug_max_len(&self, max: ::core::primitive::usize) -> __DebugMaxLen<'_, Self> {
+                __DebugMaxLen(self, max)
+            }
+        }
+
+        impl ::core::fmt::Debug for __DebugMaxLen<'_, ::core::primitive::str> {
+            fn fmt(&self, f: &mut ::core::fmt::Formatter<'_>) -> ::core::fmt::Result {
+                match self.0.char_indices().nth(self.1) {
+                    ::core::option::Option::Some((end, _)) => {
+                        let rest = self.0[end..].chars().count();
+                        ::core::fmt::Debug::fmt(&self.0[..end], f)?;
+                        ::core::write!(f, "... ({} more)", rest)
+                    }
+                    ::core::option::Option::None => ::core::fmt::Debug::fmt(self.0, f),
+                }
+            }
+        }
+
+        impl ::core::fmt::Debug for __DebugMaxLen<'_, [::core::primitive::u8]> {
+            fn fmt(&self, f: &mut ::core::fmt::Formatter<'_>) -> ::core::fmt::Result {
+                let mut list = f.debug_list();
+                list.entries(self.0.iter().take(self.1));
+                if self.0.len() > self.1 {
+                    list.entry(&::core::format_args!("... ({} more)", self.0.len() - self.1));
+                }
+                list.finish()
+            }
+        }
+    }
+}
@@ -0,0 +1,96 @@
+//! `derive(Inspect)`, handing each field that `CustomDebug` would print to a
+//! `FieldVisitor` along with the name it would be printed under.
+
+use crate::ast::{Body, Input, Variant};
+use crate::attr::{Extra, Print};
+use crate::expand::{bind_self, pattern, union_bytes, value};
+use crate::{bound, helpers};
+use proc_macro2::TokenStream;
+use quote::{format_ident, quote};
+use syn::{DeriveInput, Result};
+
+pub fn derive(input: &DeriveInput) -> Result<TokenStream> {
+    let input = Input::from_syn(input)?;
+    let ident = input.ident;
+    let (impl_generics, ty_generics, _) = input.generics.split_for_impl();
+    let where_clause = bound::debug_where_clause(&input);
+    let helpers = helpers::needed_by(&input);
+
+    let body = match &input.body {
+        Body::Struct(variant) => {
+            let bind = bind_self(variant);
+            let visits = visits(variant, &input.attrs.extras);
+            quote!(#bind #visits)
+        }
+        Body::Enum(variants) if variants.is_empty() => quote!(match *self {}),
+        Body::Enum(variants) => {
+            let arms = variants.iter().map(|variant| {
+                let ident = variant.ident;
+                let pattern = pattern(variant);
+                let visits = visits(variant, &[]);
+                quote!(Self::#ident #pattern => { #visits })
+            });
+            quote!(match self { #(#arms)* })
+        }
+        Body::Union(variant) if variant.fields.is_empty() => match &input.attrs.unsafe_raw_bytes {
+            Some(_) => {
+                let bytes = union_bytes();
+                quote!(__visitor.field("bytes", #bytes);)
+            }
+            None => TokenStream::new(),
+        },
+        Body::Union(variant) => {
+            let bind = bind_self(variant);
+            let visits = visits(variant, &[]);
+            quote!(#bind #visits)
+        }
+    };
+
+    Ok(quote! {
+        impl #impl_generics ::derive_debug::Inspect for #ident #ty_generics #where_clause {
+            fn inspect(&self, __visitor: &mut dyn ::derive_debug::FieldVisitor) {
+                #helpers
+                #body
+            }
+        }
+    })
+}
+
+/// Visits each field of the variant, once its fields are bound.
+fn visits(variant: &Variant, extras: &[Extra]) -> TokenStream {
+    let fields = variant.order.iter().map(|&i| {
+        let field = &variant.fields[i];
+        let visit = match &field.attrs.print {
+            Print::Skip => return TokenStream::new(),
+            Print::Flatten => {
+                let value = value(variant, i);
+                quote!(::derive_debug::Inspect::inspect(#value, __visitor);)
+            }
+            _ => {
+                let name = &field.name;
+                let value = value(variant, i);
+                quote!(__visitor.field(#name, #value);)
+            }
+        };
+        match &field.attrs.skip_if {
+            Some(predicate) => {
+                let binding = format_ident!("__self_{}", i);
+                quote! {
+                    if !#predicate(#binding) {
+                        #visit
+                    }
+                }
+            }
+            None => visit,
+        }
+    });
+    let extras = extras.iter().map(|extra| {
+        let name = &extra.name;
+        let expr = &extra.expr;
+        quote!(__visitor.field(#name, &(#expr));)
+    });
+    quote! {
+        #(#fields)*
+        #(#extras)*
+    }
+}
@@ -1,8 +1,365 @@
-use proc_macro::TokenStream;
+// Crates that have the "proc-macro" crate type are only allowed to export
+// procedural macros, so the traits that `derive(Inspect)` implements live in
+// this crate and the derives themselves in derive_debug-impl. They are
+// re-exported from here so that users only have one crate to import.
 
-#[proc_macro_derive(CustomDebug)]
-pub fn derive(input: TokenStream) -> TokenStream {
-    let _ = input;
+#![no_std]
 
-    unimplemented!()
+// Only `DebugDiff`, and `debug(hex)` on a `Vec` or `Box`, need an allocator.
+// The `alloc` feature that provides them is on by default; targets without an
+// allocator turn it off with `default-features = false` and keep the rest.
+#[cfg(feature = "alloc")]
+extern crate alloc;
+
+#[cfg(feature = "alloc")]
+use alloc::string::String;
+use core::fmt::Debug;
+
+pub use derive_debug_impl::{CustomDebug, CustomDisplay, Inspect};
+
+#[cfg(feature = "alloc")]
+pub use derive_debug_impl::DebugDiff;
+
+/// Receives the fields of a value one at a time, as printed by its
+/// `CustomDebug` impl.
+///
+/// Implement this to feed the same fields to something other than
+/// `fmt::Debug`, such as a key=value or JSON logger.
+pub trait FieldVisitor {
+    /// Called once for every field that is printed, in order, with the name
+    /// it is printed under and the value after any format attributes have
+    /// been applied. Tuple fields are named by their index.
+    fn field(&mut self, name: &str, value: &dyn Debug);
+}
+
+/// A value whose fields can be visited, implemented by `derive(Inspect)`.
+///
+/// The derive honors the same `#[debug(...)]` attributes as `CustomDebug`:
+/// skipped fields are not visited, renamed fields are visited under their new
+/// name, and formatted fields are visited as formatted. For an enum, the
+/// fields of the current variant are visited.
+pub trait Inspect {
+    fn inspect(&self, visitor: &mut dyn FieldVisitor);
+}
+
+/// A field-by-field comparison of two values, implemented by
+/// `derive(DebugDiff)`.
+///
+/// Fields are compared by how `CustomDebug` would print them, so format
+/// attributes apply, and skipped fields are not compared. A field whose type
+/// also implements `DebugDiff` is compared field by field in turn. That is
+/// decided where the impl is compiled, so a field whose type is a type
+/// parameter is compared by its `Debug` output, unless it is marked
+/// `#[debug(diff)]`, which compares it with `DebugDiff` and requires the
+/// field type to implement it.
+///
+/// Requires the `alloc` feature, which is on by default.
+#[cfg(feature = "alloc")]
+pub trait DebugDiff {
+    /// Lists the fields that differ, one per line as `path: left -> right`,
+    /// or returns `None` if none do.
+    fn debug_diff(&self, other: &Self) -> Option<String>;
+}
+
+#[doc(hidden)]
+pub mod __private {
+    use core::fmt::DebugStruct;
+
+    pub use self::presets::*;
+
+    #[cfg(feature = "alloc")]
+    pub use self::diff::*;
+
+    /// The entries of a struct with named fields, implemented by
+    /// `derive(CustomDebug)` so that `debug(flatten)` can add them to the
+    /// output of the struct holding it. Returns whether any entry was left
+    /// out at runtime.
+    pub trait DebugFields {
+        fn __debug_fields(&self, builder: &mut DebugStruct<'_, '_>) -> bool;
+    }
+
+    /// Support for `debug(hex)`, `debug(binary)`, `debug(bytes)` and
+    /// `debug(hexdump)`.
+    mod presets {
+        #[cfg(feature = "alloc")]
+        use alloc::{boxed::Box, vec::Vec};
+        use core::fmt::{self, Debug, Formatter};
+
+        /// Prints a value in hex or binary, the way `{:x?}` would.
+        pub struct DebugRadix<'a>(&'a dyn Radix, u32);
+
+        impl<'a> DebugRadix<'a> {
+            /// The bound is only checked here, where the derive puts the span
+            /// of the `hex` or `binary` key, so that an unsupported field
+            /// type is reported there and only once.
+            pub fn new<T: Radix>(value: &'a T, radix: u32) -> Self {
+                DebugRadix(value, radix)
+            }
+        }
+
+        impl Debug for DebugRadix<'_> {
+            fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+                self.0.__fmt_radix(self.1, f)
+            }
+        }
+
+        /// Integers, and containers of them printed element by element.
+        #[diagnostic::on_unimplemented(
+            message = "`{Self}` cannot be printed with `debug(hex)` or `debug(binary)`",
+            label = "expected an integer, or an array, slice, `Vec`, `Box` or `Option` of them"
+        )]
+        pub trait Radix {
+            fn __fmt_radix(&self, radix: u32, f: &mut Formatter<'_>) -> fmt::Result;
+        }
+
+        macro_rules! radix_ints {
+            ($($int:ty)*) => {$(
+                impl Radix for $int {
+                    fn __fmt_radix(&self, radix: u32, f: &mut Formatter<'_>) -> fmt::Result {
+                        if radix == 16 {
+                            fmt::LowerHex::fmt(self, f)
+                        } else {
+                            fmt::Binary::fmt(self, f)
+                        }
+                    }
+                }
+            )*};
+        }
+
+        radix_ints!(u8 u16 u32 u64 u128 usize i8 i16 i32 i64 i128 isize);
+
+        impl<T: Radix> Radix for [T] {
+            fn __fmt_radix(&self, radix: u32, f: &mut Formatter<'_>) -> fmt::Result {
+                f.debug_list()
+                    .entries(self.iter().map(|item| DebugRadix(item, radix)))
+                    .finish()
+            }
+        }
+
+        impl<T: Radix, const N: usize> Radix for [T; N] {
+            fn __fmt_radix(&self, radix: u32, f: &mut Formatter<'_>) -> fmt::Result {
+                self[..].__fmt_radix(radix, f)
+            }
+        }
+
+        impl<T: Radix + ?Sized> Radix for &T {
+            fn __fmt_radix(&self, radix: u32, f: &mut Formatter<'_>) -> fmt::Result {
+                (**self).__fmt_radix(radix, f)
+            }
+        }
+
+        impl<T: Radix + ?Sized> Radix for &mut T {
+            fn __fmt_radix(&self, radix: u32, f: &mut Formatter<'_>) -> fmt::Result {
+                (**self).__fmt_radix(radix, f)
+            }
+        }
+
+        #[cfg(feature = "alloc")]
+        impl<T: Radix + ?Sized> Radix for Box<T> {
+            fn __fmt_radix(&self, radix: u32, f: &mut Formatter<'_>) -> fmt::Result {
+                (**self).__fmt_radix(radix, f)
+            }
+        }
+
+        #[cfg(feature = "alloc")]
+        impl<T: Radix> Radix for Vec<T> {
+            fn __fmt_radix(&self, radix: u32, f: &mut Formatter<'_>) -> fmt::Result {
+                self[..].__fmt_radix(radix, f)
+            }
+        }
+
+        impl<T: Radix> Radix for Option<T> {
+            fn __fmt_radix(&self, radix: u32, f: &mut Formatter<'_>) -> fmt::Result {
+                match self {
+                    Some(value) => f
+                        .debug_tuple("Some")
+                        .field(&DebugRadix(value, radix))
+                        .finish(),
+                    None => f.write_str("None"),
+                }
+            }
+        }
+
+        /// Prints bytes as an escaped byte string literal, or as a hex dump,
+        /// which under `{:#?}` is laid out sixteen bytes to a line with
+        /// offsets and an ASCII column.
+        pub struct DebugBytes<'a, T: ?Sized>(&'a T, bool);
+
+        impl<T: Bytes + ?Sized> Debug for DebugBytes<'_, T> {
+            fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+                self.0.__fmt_bytes(self.1, f)
+            }
+        }
+
+        /// Byte slices, and `Option`s of anything that is `AsRef<[u8]>`. The
+        /// derive calls `__debug_bytes` as a method, so that anything that
+        /// derefs to `[u8]` is accepted too.
+        pub trait Bytes {
+            fn __debug_bytes(&self, dump: bool) -> DebugBytes<'_, Self> {
+                DebugBytes(self, dump)
+            }
+
+            fn __fmt_bytes(&self, dump: bool, f: &mut Formatter<'_>) -> fmt::Result;
+        }
+
+        impl Bytes for [u8] {
+            fn __fmt_bytes(&self, dump: bool, f: &mut Formatter<'_>) -> fmt::Result {
+                if !dump {
+                    f.write_str("b\"")?;
+                    for &byte in self {
+                        if byte == b'\'' {
+                            f.write_str("'")?;
+                        } else {
+                            fmt::Display::fmt(&core::ascii::escape_default(byte), f)?;
+                        }
+                    }
+                    return f.write_str("\"");
+                }
+                if !f.alternate() {
+                    for (i, byte) in self.iter().enumerate() {
+                        let sep = if i == 0 { "" } else { " " };
+                        write!(f, "{}{:02x}", sep, byte)?;
+                    }
+                    return Ok(());
+                }
+                for (i, line) in self.chunks(16).enumerate() {
+                    if i > 0 {
+                        f.write_str("\n")?;
+                    }
+                    write!(f, "{:08x} ", i * 16)?;
+                    for column in 0..16 {
+                        match line.get(column) {
+                            Some(byte) => write!(f, " {:02x}", byte)?,
+                            None => f.write_str("   ")?,
+                        }
+                    }
+                    f.write_str("  |")?;
+                    for &byte in line {
+                        let ch = if byte.is_ascii_graphic() || byte == b' ' {
+                            byte as char
+                        } else {
+                            '.'
+                        };
+                        fmt::Write::write_char(f, ch)?;
+                    }
+                    f.write_str("|")?;
+                }
+                Ok(())
+            }
+        }
+
+        impl<T: AsRef<[u8]>> Bytes for Option<T> {
+            fn __fmt_bytes(&self, dump: bool, f: &mut Formatter<'_>) -> fmt::Result {
+                match self {
+                    Some(value) => f
+                        .debug_tuple("Some")
+                        .field(&DebugBytes(value.as_ref(), dump))
+                        .finish(),
+                    None => f.write_str("None"),
+                }
+            }
+        }
+    }
+
+    /// Support for `derive(DebugDiff)`.
+    #[cfg(feature = "alloc")]
+    mod diff {
+        use crate::{DebugDiff, FieldVisitor};
+        use alloc::format;
+        use alloc::vec::Vec;
+        use core::fmt::Debug;
+
+        pub use alloc::string::String;
+
+        /// The fields that `derive(DebugDiff)` compares by their formatted
+        /// output rather than by recursing into them, visited in order. Kept
+        /// apart from `DebugDiff` so that hand-written impls need not provide
+        /// it.
+        pub trait DiffLeaves {
+            fn __debug_diff_leaves(&self, visitor: &mut dyn FieldVisitor);
+        }
+
+        /// The lines of a diff under construction.
+        #[derive(Default)]
+        pub struct Diff {
+            lines: Vec<String>,
+        }
+
+        impl Diff {
+            pub fn leaf(&mut self, name: &str, left: &dyn Debug, right: &dyn Debug) {
+                self.strings(name, &format!("{:?}", left), &format!("{:?}", right));
+            }
+
+            pub fn strings(&mut self, name: &str, left: &str, right: &str) {
+                if left == right {
+                    return;
+                }
+                self.lines.push(if name.is_empty() {
+                    format!("{} -> {}", left, right)
+                } else {
+                    format!("{}: {} -> {}", name, left, right)
+                });
+            }
+
+            /// Adds the lines of a nested diff under `name`, or as they are for
+            /// a flattened field, whose name is empty.
+            pub fn nested(&mut self, name: &str, diff: Option<String>) {
+                for line in diff.iter().flat_map(|diff| diff.lines()) {
+                    self.lines.push(if name.is_empty() {
+                        String::from(line)
+                    } else {
+                        format!("{}.{}", name, line)
+                    });
+                }
+            }
+
+            pub fn variant(&mut self, left: &str, right: &str) {
+                self.lines.push(format!("variant: {} -> {}", left, right));
+            }
+
+            pub fn finish(self) -> Option<String> {
+                if self.lines.is_empty() {
+                    None
+                } else {
+                    Some(self.lines.join("\n"))
+                }
+            }
+        }
+
+        /// Collects formatted fields, for `DiffLeaves::__debug_diff_leaves`.
+        #[derive(Default)]
+        pub struct Leaves(pub Vec<String>);
+
+        impl FieldVisitor for Leaves {
+            fn field(&mut self, _name: &str, value: &dyn Debug) {
+                self.0.push(format!("{:?}", value));
+            }
+        }
+
+        /// A pair of field values to compare. Method resolution picks
+        /// `DiffNested` if the field type implements `DebugDiff`, since it is
+        /// implemented for `Pair` itself, and otherwise falls back to
+        /// `DiffLeaf`, implemented for `&Pair`.
+        pub struct Pair<'a, T: ?Sized>(pub &'a T, pub &'a T);
+
+        pub trait DiffNested {
+            fn diff(&self, name: &str, diff: &mut Diff);
+        }
+
+        impl<T: ?Sized + DebugDiff> DiffNested for Pair<'_, T> {
+            fn diff(&self, name: &str, diff: &mut Diff) {
+                diff.nested(name, self.0.debug_diff(self.1));
+            }
+        }
+
+        pub trait DiffLeaf {
+            fn diff(&self, name: &str, diff: &mut Diff);
+        }
+
+        impl<T: ?Sized + Debug> DiffLeaf for &Pair<'_, T> {
+            fn diff(&self, name: &str, diff: &mut Diff) {
+                diff.leaf(name, &self.0, &self.1);
+            }
+        }
+    }
 }
@@ -0,0 +1,101 @@
+// Enums are printed variant by variant exactly as the standard library's
+// derive would print them: unit variants as their name, tuple variants through
+// `debug_tuple` and struct variants through `debug_struct`. Field format
+// strings and the `debug(bound = "...")` escape hatch work the same as they do
+// on structs.
+//
+//     impl<T: Trait> Debug for Message<T>
+//     where
+//         T::Value: Debug,
+//     {
+//         fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+//             match self {
+//                 Self::Ping => f.write_str("Ping"),
+//                 Self::Data(__self_0, __self_1) => f
+//                     .debug_tuple("Data")
+//                     .field(__self_0)
+//                     .field(&format_args!("0x{:02x}", __self_1))
+//                     .finish(),
+//                 ...
+//             }
+//         }
+//     }
+
+use derive_debug::CustomDebug;
+use std::fmt::Debug;
+
+pub trait Trait {
+    type Value;
+}
+
+#[derive(CustomDebug)]
+pub enum Message<T: Trait> {
+    Ping,
+    Pair(u32, i8),
+    Data(u32, #[debug = "0x{:02x}"] u8),
+    Error {
+        code: i32,
+        #[debug = "{:?}!"]
+        reason: &'static str,
+    },
+    Values {
+        values: Vec<T::Value>,
+    },
+    Empty {},
+}
+
+#[derive(CustomDebug)]
+#[debug(bound = "T::Value: Debug")]
+pub enum Wrapper<T: Trait> {
+    Message(Message<T>),
+}
+
+#[derive(CustomDebug)]
+pub enum Never {}
+
+#[derive(Debug)]
+pub enum Std {
+    Ping,
+    Pair(u32, i8),
+    Error { code: i32, reason: &'static str },
+    Empty {},
+}
+
+fn assert_debug<F: Debug>() {}
+
+fn main() {
+    struct Id;
+
+    impl Trait for Id {
+        type Value = u8;
+    }
+
+    assert_debug::<Wrapper<Id>>();
+    assert_debug::<Never>();
+
+    let cases: [(Message<Id>, Std); 3] = [
+        (Message::Ping, Std::Ping),
+        (Message::Pair(7, -1), Std::Pair(7, -1)),
+        (Message::Empty {}, Std::Empty {}),
+    ];
+    for (custom, std) in &cases {
+        assert_eq!(format!("{:?}", custom), format!("{:?}", std));
+        assert_eq!(format!("{:#?}", custom), format!("{:#?}", std));
+    }
+
+    let data = Message::<Id>::Data(7, 255);
+    assert_eq!(format!("{:?}", data), "Data(7, 0xff)");
+
+    let error = Message::<Id>::Error {
+        code: -1,
+        reason: "eof",
+    };
+    assert_eq!(format!("{:?}", error), r#"Error { code: -1, reason: "eof"! }"#);
+    let _ = Std::Error {
+        code: -1,
+        reason: "eof",
+    };
+
+    let values = Wrapper::Message(Message::<Id>::Values { values: vec![1, 2] });
+    assert_eq!(format!("{:?}", values), "Message(Values { values: [1, 2] })");
+}
@@ -0,0 +1,72 @@
+// Tuple structs print through `debug_tuple` and unit structs print as their
+// name, matching the standard library's derive.
+//
+// A single-field struct marked `#[debug(transparent)]` prints as nothing more
+// than its field, which suits newtypes such as identifiers:
+//
+//     impl Debug for UserId {
+//         fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+//             let Self { 0: __self_0 } = self;
+//             Debug::fmt(__self_0, f)
+//         }
+//     }
+
+use derive_debug::CustomDebug;
+
+#[derive(CustomDebug)]
+pub struct Point(i32, #[debug = "{}px"] i32);
+
+#[derive(CustomDebug)]
+pub struct Marker;
+
+#[derive(CustomDebug)]
+#[debug(transparent)]
+pub struct UserId(u64);
+
+#[derive(CustomDebug)]
+#[debug(transparent)]
+pub struct Mask {
+    #[debug = "0b{:04b}"]
+    bits: u8,
+}
+
+#[derive(CustomDebug)]
+pub struct Session {
+    user: UserId,
+    marker: Marker,
+}
+
+mod std_derive {
+    #[derive(Debug)]
+    pub struct Point(pub i32, pub i32);
+
+    #[derive(Debug)]
+    pub struct Marker;
+}
+
+fn main() {
+    let point = Point(1, 2);
+    let std_point = std_derive::Point(1, 2);
+    assert_eq!(format!("{:?}", point), "Point(1, 2px)");
+    assert_eq!(
+        format!("{:#?}", point),
+        format!("{:#?}", std_point).replace("2,", "2px,"),
+    );
+    assert_eq!(format!("{:?}", Marker), format!("{:?}", std_derive::Marker));
+    assert_eq!(
+        format!("{:#?}", Marker),
+        format!("{:#?}", std_derive::Marker)
+    );
+
+    assert_eq!(format!("{:?}", UserId(7)), "7");
+    assert_eq!(format!("{:?}", Mask { bits: 5 }), "0b0101");
+
+    let session = Session {
+        user: UserId(7),
+        marker: Marker,
+    };
+    assert_eq!(
+        format!("{:?}", session),
+        "Session { user: 7, marker: Marker }"
+    );
+}
@@ -0,0 +1,17 @@
+// `debug(transparent)` has no single field to defer to on a struct with more
+// than one field, or on an enum.
+
+use derive_debug::CustomDebug;
+
+#[derive(CustomDebug)]
+#[debug(transparent)]
+pub struct Pair(u32, u32);
+
+#[derive(CustomDebug)]
+#[debug(transparent)]
+pub enum Either {
+    Left(u32),
+    Right(u32),
+}
+
+fn main() {}
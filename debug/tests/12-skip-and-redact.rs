@@ -0,0 +1,58 @@
+// Fields holding secrets can be left out of the output with `debug(skip)`, or
+// printed as a placeholder with `debug(redact)`. The placeholder is
+// `<redacted>` unless a different one is given. Neither needs the field type
+// to implement Debug, so no bound is inferred for them.
+
+use derive_debug::CustomDebug;
+use std::fmt::Debug;
+
+#[derive(CustomDebug)]
+pub struct Credentials<T> {
+    user: &'static str,
+    #[debug(redact)]
+    password: String,
+    #[debug(redact = "***")]
+    token: T,
+    #[debug(skip)]
+    session_key: T,
+}
+
+#[derive(CustomDebug)]
+pub enum Auth {
+    Anonymous,
+    Basic(&'static str, #[debug(redact)] &'static str),
+    Bearer {
+        #[debug(skip)]
+        token: &'static str,
+        expires: u64,
+    },
+}
+
+fn assert_debug<F: Debug>() {}
+
+fn main() {
+    struct NotDebug;
+
+    assert_debug::<Credentials<NotDebug>>();
+
+    let credentials = Credentials {
+        user: "root",
+        password: "hunter2".to_owned(),
+        token: NotDebug,
+        session_key: NotDebug,
+    };
+    assert_eq!(
+        format!("{:?}", credentials),
+        r#"Credentials { user: "root", password: <redacted>, token: *** }"#,
+    );
+
+    let basic = Auth::Basic("root", "hunter2");
+    assert_eq!(format!("{:?}", basic), r#"Basic("root", <redacted>)"#);
+
+    let bearer = Auth::Bearer {
+        token: "abc",
+        expires: 60,
+    };
+    assert_eq!(format!("{:?}", bearer), "Bearer { expires: 60 }");
+    assert_eq!(format!("{:?}", Auth::Anonymous), "Anonymous");
+}
@@ -0,0 +1,67 @@
+// A field can be printed by an arbitrary function instead of a format string:
+//
+//     #[debug(with = "fmt_ms")]
+//     elapsed: Duration,
+//
+// where the function has the signature
+//
+//     fn fmt_ms(value: &Duration, f: &mut fmt::Formatter) -> fmt::Result
+//
+// The macro wraps the function in a small adapter type whose Debug impl calls
+// it, so that it can be handed to `debug_struct().field()` like any other
+// value. The field type itself does not need to implement Debug.
+
+use derive_debug::CustomDebug;
+use std::fmt;
+use std::time::Duration;
+
+mod hash {
+    use std::fmt;
+
+    pub fn short(bytes: &[u8; 32], f: &mut fmt::Formatter) -> fmt::Result {
+        for byte in &bytes[..4] {
+            write!(f, "{:02x}", byte)?;
+        }
+        f.write_str("…")
+    }
+}
+
+fn fmt_ms(value: &Duration, f: &mut fmt::Formatter) -> fmt::Result {
+    write!(f, "{}ms", value.as_millis())
+}
+
+fn fmt_len<T>(value: &Vec<T>, f: &mut fmt::Formatter) -> fmt::Result {
+    write!(f, "{} items", value.len())
+}
+
+#[derive(CustomDebug)]
+pub struct Block<T> {
+    #[debug(with = "hash::short")]
+    hash: [u8; 32],
+    #[debug(with = "fmt_ms")]
+    elapsed: Duration,
+    #[debug(with = "fmt_len")]
+    items: Vec<T>,
+}
+
+#[derive(CustomDebug)]
+pub enum Event {
+    Timeout(#[debug(with = "fmt_ms")] Duration),
+}
+
+fn main() {
+    struct NotDebug;
+
+    let block = Block {
+        hash: [0xab; 32],
+        elapsed: Duration::from_micros(1500),
+        items: vec![NotDebug, NotDebug],
+    };
+    assert_eq!(
+        format!("{:?}", block),
+        "Block { hash: abababab…, elapsed: 1ms, items: 2 items }",
+    );
+
+    let event = Event::Timeout(Duration::from_secs(2));
+    assert_eq!(format!("{:?}", event), "Timeout(2000ms)");
+}
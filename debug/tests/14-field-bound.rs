@@ -0,0 +1,69 @@
+// A `debug(bound = "...")` attribute on an individual field substitutes only
+// the bounds that would have been inferred from that field's type, without
+// removing the bounds inferred from the other fields:
+//
+//     impl<T: Trait, U> Debug for Wrapper<T, U>
+//     where
+//         T::Value: Debug,
+//         U: Debug,
+//     {...}
+//
+// An empty string asks for no bound at all on behalf of the field.
+
+use derive_debug::CustomDebug;
+use std::fmt::Debug;
+use std::rc::Rc;
+
+pub trait Trait {
+    type Value;
+}
+
+#[derive(CustomDebug)]
+pub struct Wrapper<T: Trait, U> {
+    #[debug(bound = "T::Value: Debug")]
+    field: Field<T>,
+    normal: U,
+}
+
+#[derive(CustomDebug)]
+pub struct Field<T: Trait> {
+    values: Vec<T::Value>,
+}
+
+/// Prints the address of the pointee, which needs no bound on `T`.
+pub struct Addr<T>(Rc<T>);
+
+impl<T> Debug for Addr<T> {
+    fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+        write!(f, "{:p}", self.0)
+    }
+}
+
+#[derive(CustomDebug)]
+pub struct Shared<T> {
+    #[debug(bound = "")]
+    value: Addr<T>,
+}
+
+fn assert_debug<F: Debug>() {}
+
+fn main() {
+    struct Id;
+    struct NotDebug;
+
+    impl Trait for Id {
+        type Value = u8;
+    }
+
+    assert_debug::<Wrapper<Id, u8>>();
+    assert_debug::<Shared<NotDebug>>();
+
+    let wrapper = Wrapper::<Id, _> {
+        field: Field { values: vec![1] },
+        normal: "x",
+    };
+    assert_eq!(
+        format!("{:?}", wrapper),
+        r#"Wrapper { field: Field { values: [1] }, normal: "x" }"#,
+    );
+}
@@ -0,0 +1,106 @@
+// Bound inference looks for type parameters and associated types of type
+// parameters anywhere inside a field type, and never bounds the field type as
+// a whole. Each row of the table below declares a struct with a single field
+// of the given type and then checks that the struct implements Debug for the
+// given type arguments. The arguments are chosen so that a bound which is
+// stronger than necessary, such as `T: Debug` when only `T::Value: Debug` is
+// needed, would make the check fail.
+//
+//     Name[generics] (field type) for [type arguments];
+
+use derive_debug::CustomDebug;
+use std::fmt::Debug;
+use std::marker::PhantomData;
+
+pub trait Trait {
+    type Value;
+}
+
+/// Does not implement Debug, but its associated type does.
+pub struct Id;
+
+impl Trait for Id {
+    type Value = u8;
+}
+
+/// Does not implement Debug, and neither does anything derived from it.
+pub struct NotDebug;
+
+fn assert_debug<F: Debug>() {}
+
+macro_rules! table {
+    ($($name:ident [$($generics:tt)*] ($ty:ty) for [$($arg:ty),*];)*) => {
+        $(
+            #[derive(CustomDebug)]
+            pub struct $name<$($generics)*> {
+                field: $ty,
+            }
+        )*
+
+        fn check_table() {
+            $(
+                assert_debug::<$name<$($arg),*>>();
+            )*
+        }
+    };
+}
+
+table! {
+    Param[T] (T) for [u8];
+    Reference[T: 'static] (&'static T) for [u8];
+    SliceReference[T: 'static] (&'static [T]) for [u8];
+    Tuple[T, U] ((T, Option<U>)) for [u8, u8];
+    Array[T] ([T; 4]) for [u8];
+
+    Assoc[T: Trait] (T::Value) for [Id];
+    NestedAssoc[T: Trait] (Option<Box<T::Value>>) for [Id];
+    QualifiedAssoc[T: Trait] (<T as Trait>::Value) for [Id];
+    NestedQualifiedAssoc[T: Trait] (Vec<Option<Box<<T as Trait>::Value>>>) for [Id];
+    AssocOfContainer[T] (<Vec<T> as IntoIterator>::Item) for [u8];
+    AssocReference[T: Trait + 'static] (&'static T::Value) for [Id];
+
+    Phantom[T] (PhantomData<T>) for [NotDebug];
+    PhantomFn[T] (PhantomData<fn() -> T>) for [NotDebug];
+    PhantomReference[T: 'static] (PhantomData<&'static T>) for [NotDebug];
+    NestedPhantom[T] (Option<PhantomData<T>>) for [NotDebug];
+    QualifiedPhantom[T] (std::marker::PhantomData<T>) for [NotDebug];
+    FnPointer[T] (fn(T) -> T) for [NotDebug];
+    FnPointerInside[T] (Vec<fn(&T) -> bool>) for [NotDebug];
+}
+
+// Recursive types, which would send the trait solver into an infinite loop
+// under `FieldType: Debug` bounds.
+
+#[derive(CustomDebug)]
+pub struct List<T> {
+    head: T,
+    tail: Option<Box<List<T>>>,
+}
+
+#[derive(CustomDebug)]
+pub struct Tree<T> {
+    value: T,
+    children: Vec<Tree<T>>,
+    parent: Option<PhantomData<Tree<T>>>,
+}
+
+#[derive(CustomDebug)]
+pub enum Expr<T> {
+    Leaf(T),
+    Neg(Box<Expr<T>>),
+    Add(Box<Expr<T>>, Box<Expr<T>>),
+}
+
+fn main() {
+    check_table();
+
+    assert_debug::<List<u8>>();
+    assert_debug::<Tree<u8>>();
+    assert_debug::<Expr<u8>>();
+
+    let expr = Expr::Add(
+        Box::new(Expr::Leaf(1)),
+        Box::new(Expr::Neg(Box::new(Expr::Leaf(2)))),
+    );
+    assert_eq!(format!("{:?}", expr), "Add(Leaf(1), Neg(Leaf(2)))");
+}
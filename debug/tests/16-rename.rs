@@ -0,0 +1,48 @@
+// The printed names can differ from the Rust identifiers: `debug(name = "...")`
+// on a struct or on an enum variant replaces the type or variant name, and
+// `debug(rename = "...")` on a named field replaces the field name.
+
+use derive_debug::CustomDebug;
+
+#[derive(CustomDebug)]
+#[debug(name = "Cmd")]
+pub struct ExecuteCommandRequestV2 {
+    #[debug(rename = "exe")]
+    executable_path: &'static str,
+    #[debug(rename = "argv")]
+    arguments: Vec<&'static str>,
+    cwd: &'static str,
+}
+
+#[derive(CustomDebug)]
+#[debug(name = "Id")]
+pub struct RequestIdentifier(u64);
+
+#[derive(CustomDebug)]
+pub enum Reply {
+    #[debug(name = "ok")]
+    Success,
+    #[debug(name = "err")]
+    Failure {
+        #[debug(rename = "errno")]
+        error_code: i32,
+    },
+}
+
+fn main() {
+    let command = ExecuteCommandRequestV2 {
+        executable_path: "cargo",
+        arguments: vec!["build"],
+        cwd: "..",
+    };
+    assert_eq!(
+        format!("{:?}", command),
+        r#"Cmd { exe: "cargo", argv: ["build"], cwd: ".." }"#,
+    );
+    assert_eq!(format!("{:?}", RequestIdentifier(7)), "Id(7)");
+    assert_eq!(format!("{:?}", Reply::Success), "ok");
+    assert_eq!(
+        format!("{:?}", Reply::Failure { error_code: 2 }),
+        "err { errno: 2 }",
+    );
+}
@@ -0,0 +1,70 @@
+// `debug(skip_if = "...")` names a predicate that is called with a reference
+// to the field at runtime. When it returns true the field is left out, and the
+// output ends in `..` to show that something was omitted, the same way as
+// `DebugStruct::finish_non_exhaustive`.
+//
+//     impl Debug for Record {
+//         fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+//             match self {
+//                 Self { id: __self_0, parent: __self_1, tags: __self_2 } => {
+//                     let mut __builder = f.debug_struct("Record");
+//                     let mut __skipped = false;
+//                     __builder.field("id", __self_0);
+//                     if Option::is_none(__self_1) {
+//                         __skipped = true;
+//                     } else {
+//                         __builder.field("parent", __self_1);
+//                     }
+//                     ...
+//                     if __skipped {
+//                         __builder.finish_non_exhaustive()
+//                     } else {
+//                         __builder.finish()
+//                     }
+//                 }
+//             }
+//         }
+//     }
+
+use derive_debug::CustomDebug;
+
+fn is_zero(value: &u32) -> bool {
+    *value == 0
+}
+
+#[derive(CustomDebug)]
+pub struct Record {
+    id: u32,
+    #[debug(skip_if = "Option::is_none")]
+    parent: Option<u32>,
+    #[debug(skip_if = "Vec::is_empty")]
+    tags: Vec<&'static str>,
+}
+
+#[derive(CustomDebug)]
+pub enum Sample {
+    Counts(#[debug(skip_if = "is_zero")] u32, #[debug(skip_if = "is_zero")] u32),
+}
+
+fn main() {
+    let full = Record {
+        id: 1,
+        parent: Some(0),
+        tags: vec!["a"],
+    };
+    assert_eq!(
+        format!("{:?}", full),
+        r#"Record { id: 1, parent: Some(0), tags: ["a"] }"#,
+    );
+
+    let sparse = Record {
+        id: 1,
+        parent: None,
+        tags: Vec::new(),
+    };
+    assert_eq!(format!("{:?}", sparse), "Record { id: 1, .. }");
+    assert_eq!(format!("{:#?}", sparse), "Record {\n    id: 1,\n    ..\n}");
+
+    assert_eq!(format!("{:?}", Sample::Counts(3, 0)), "Counts(3, ..)");
+    assert_eq!(format!("{:?}", Sample::Counts(3, 4)), "Counts(3, 4)");
+}
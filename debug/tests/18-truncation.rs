@@ -0,0 +1,79 @@
+// Large fields can be cut short so that a single value cannot flood the
+// output. `debug(max_items = N)` prints at most N elements of anything that
+// can be iterated by reference, and `debug(max_len = N)` prints at most N
+// characters of a string or N bytes of a byte slice. Whatever was left out is
+// counted by a trailing `... (N more)` marker. Elements are counted, not
+// the printed text, so quotes or brackets in what an element prints do not
+// throw the count off.
+
+use derive_debug::CustomDebug;
+use std::collections::BTreeSet;
+
+#[derive(CustomDebug)]
+pub struct Upload<T> {
+    #[debug(max_len = 5)]
+    name: String,
+    #[debug(max_len = 4)]
+    payload: Vec<u8>,
+    #[debug(max_items = 3)]
+    chunks: Vec<T>,
+    #[debug(max_items = 2)]
+    tags: BTreeSet<&'static str>,
+    #[debug(max_len = 8)]
+    comment: &'static str,
+    #[debug(max_len = 2)]
+    magic: [u8; 4],
+}
+
+#[derive(CustomDebug)]
+pub struct Secret {
+    #[debug(redact = "can't show")]
+    key: u32,
+    opening: char,
+}
+
+#[derive(CustomDebug)]
+pub struct Vault {
+    #[debug(max_items = 2)]
+    secrets: Vec<Secret>,
+    count: u32,
+}
+
+fn main() {
+    let upload = Upload {
+        name: "résumé.pdf".to_owned(),
+        payload: vec![0; 10 * 1024 * 1024],
+        chunks: vec![1, 2, 3, 4, 5],
+        tags: ["a", "b"].into_iter().collect(),
+        comment: "short",
+        magic: *b"%PDF",
+    };
+
+    assert_eq!(
+        format!("{:?}", upload),
+        concat!(
+            r#"Upload { name: "résum"... (5 more), "#,
+            "payload: [0, 0, 0, 0, ... (10485756 more)], ",
+            "chunks: [1, 2, 3, ... (2 more)], ",
+            r#"tags: ["a", "b"], "#,
+            r#"comment: "short", "#,
+            "magic: [37, 80, ... (2 more)] }",
+        ),
+    );
+
+    let secret = |key, opening| Secret { key, opening };
+    let vault = Vault {
+        secrets: vec![secret(1, '('), secret(2, '['), secret(3, '{')],
+        count: 3,
+    };
+    assert_eq!(
+        format!("{:?}", vault),
+        concat!(
+            "Vault { secrets: [",
+            "Secret { key: can't show, opening: '(' }, ",
+            "Secret { key: can't show, opening: '[' }, ",
+            "... (1 more)], ",
+            "count: 3 }",
+        ),
+    );
+}
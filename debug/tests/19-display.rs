@@ -0,0 +1,91 @@
+// A second derive, CustomDisplay, implements Display from a template written
+// on the struct, or on each variant of an enum:
+//
+//     #[derive(CustomDisplay)]
+//     #[display("{executable} {args:?}")]
+//     pub struct Command {
+//         executable: String,
+//         args: Vec<String>,
+//     }
+//
+// Placeholders take the same format specs as `format!`, but instead of
+// positional arguments they refer to the fields: by name on structs with named
+// fields, by index on tuple structs. Widths and precisions may also be read
+// from a field with the `name$` syntax.
+//
+// Each field is only bounded by the traits its placeholders use, so `T` below
+// needs Display and `U` needs Debug, and neither needs anything more.
+
+use derive_debug::CustomDisplay;
+use std::fmt::Debug;
+
+#[derive(CustomDisplay)]
+#[display("{executable} {args:?}")]
+pub struct Command {
+    executable: String,
+    args: Vec<String>,
+}
+
+#[derive(CustomDisplay)]
+#[display("({0}, {1:.2})")]
+pub struct Point(i32, f64);
+
+#[derive(CustomDisplay)]
+pub enum Shape {
+    #[display("circle of radius {radius}")]
+    Circle { radius: u32 },
+    #[display("{0}x{1} rectangle")]
+    Rect(u32, u32),
+    #[display("{{empty}}")]
+    Empty,
+}
+
+#[derive(CustomDisplay)]
+#[display("[{label:>width$}] {id:#06x}")]
+pub struct Aligned {
+    label: &'static str,
+    width: usize,
+    id: u16,
+}
+
+#[derive(CustomDisplay)]
+#[display("{value} ({meta:?})")]
+pub struct Tagged<T, U> {
+    value: T,
+    meta: U,
+}
+
+struct OnlyDebug;
+
+impl Debug for OnlyDebug {
+    fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+        f.write_str("OnlyDebug")
+    }
+}
+
+fn main() {
+    let command = Command {
+        executable: "cargo".to_owned(),
+        args: vec!["build".to_owned(), "--release".to_owned()],
+    };
+    assert_eq!(command.to_string(), r#"cargo ["build", "--release"]"#);
+
+    assert_eq!(Point(3, 0.5).to_string(), "(3, 0.50)");
+
+    assert_eq!(Shape::Circle { radius: 2 }.to_string(), "circle of radius 2");
+    assert_eq!(Shape::Rect(3, 4).to_string(), "3x4 rectangle");
+    assert_eq!(Shape::Empty.to_string(), "{empty}");
+
+    let aligned = Aligned {
+        label: "ok",
+        width: 4,
+        id: 42,
+    };
+    assert_eq!(aligned.to_string(), "[  ok] 0x002a");
+
+    let tagged = Tagged {
+        value: 1u8,
+        meta: OnlyDebug,
+    };
+    assert_eq!(tagged.to_string(), "1 (OnlyDebug)");
+}
@@ -0,0 +1,15 @@
+// Templates are checked while the macro expands. A placeholder naming a field
+// that does not exist is reported together with the fields that could have
+// been meant. The message quotes the placeholder and its column, since on
+// stable the compiler can only underline the template as a whole.
+
+use derive_debug::CustomDisplay;
+
+#[derive(CustomDisplay)]
+#[display("{executable} {argz:?}")]
+pub struct Command {
+    executable: String,
+    args: Vec<String>,
+}
+
+fn main() {}
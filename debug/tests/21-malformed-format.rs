@@ -0,0 +1,43 @@
+// Field format strings are parsed by the derive itself, so that mistakes are
+// reported against the attribute instead of from deep inside the generated
+// `format_args!` call. Each message quotes the offending part of the literal
+// and its column, since on stable the compiler can only underline the literal
+// as a whole. The field's value is the only positional argument; names refer
+// to the other fields of the same struct.
+//
+// Each struct below is rejected with its own error.
+
+use derive_debug::CustomDebug;
+
+#[derive(CustomDebug)]
+pub struct UnknownTrait {
+    #[debug = "{:08q}"]
+    bits: u8,
+}
+
+#[derive(CustomDebug)]
+pub struct TooManyPlaceholders {
+    #[debug = "{} {}"]
+    pair: u8,
+}
+
+#[derive(CustomDebug)]
+pub struct UnknownSibling {
+    #[debug = "{} {unti}"]
+    value: f64,
+    unit: &'static str,
+}
+
+#[derive(CustomDebug)]
+pub struct Unterminated {
+    #[debug = "0b{:08b"]
+    bits: u8,
+}
+
+#[derive(CustomDebug)]
+pub struct NoPlaceholder {
+    #[debug = "bits"]
+    bits: u8,
+}
+
+fn main() {}
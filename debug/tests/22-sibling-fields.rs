@@ -0,0 +1,125 @@
+// A field's format string may refer to the other fields of the same struct or
+// variant by name. `{}` (or `{0}`) is still the field itself:
+//
+//     #[debug = "{} {unit}"]
+//     value: f64,
+//     unit: &'static str,
+//
+// Named fields may also give a width or precision, as in `{:.prec$}`.
+//
+// Separately, #[debug(expr = "...")] prints the value of an expression in place
+// of the field. The expression is evaluated inside `fmt`, so it can use `self`.
+// It combines with a format string, whose `{}` then stands for the expression.
+//
+// Bounds follow the placeholders: in `Measured<T, U>` below, `T` is printed
+// with `{}` and `U` with `{:?}`, so the impl requires `T: Display` and
+// `U: Debug`.
+
+use derive_debug::CustomDebug;
+use std::fmt::{self, Debug, Display};
+
+#[derive(CustomDebug)]
+pub struct Timing {
+    #[debug = "{} {unit}"]
+    value: f64,
+    #[debug(skip)]
+    unit: &'static str,
+}
+
+#[derive(CustomDebug)]
+pub struct Reading {
+    #[debug = "{:.prec$}"]
+    value: f64,
+    #[debug(skip)]
+    prec: usize,
+}
+
+#[derive(CustomDebug)]
+pub struct Queue {
+    #[debug(expr = "self.items.len()")]
+    len: (),
+    #[debug(expr = "self.items.first()", rename = "head")]
+    items: Vec<u32>,
+}
+
+#[derive(CustomDebug)]
+pub struct Inventory {
+    #[debug(expr = "self.items.len()")]
+    #[debug = "{} of {capacity}"]
+    items: Vec<&'static str>,
+    capacity: usize,
+}
+
+#[derive(CustomDebug)]
+pub enum Sample {
+    Scaled {
+        #[debug = "{}x{scale:?}"]
+        value: i32,
+        scale: u8,
+    },
+}
+
+#[derive(CustomDebug)]
+pub struct Measured<T, U> {
+    #[debug = "{} ({unit:?})"]
+    value: T,
+    #[debug(skip)]
+    unit: U,
+}
+
+struct OnlyDisplay;
+
+impl Display for OnlyDisplay {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.write_str("42")
+    }
+}
+
+struct OnlyDebug;
+
+impl Debug for OnlyDebug {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.write_str("ms")
+    }
+}
+
+fn main() {
+    let timing = Timing {
+        value: 3.5,
+        unit: "ms",
+    };
+    assert_eq!(format!("{:?}", timing), "Timing { value: 3.5 ms }");
+
+    let reading = Reading {
+        value: 1.0 / 3.0,
+        prec: 2,
+    };
+    assert_eq!(format!("{:?}", reading), "Reading { value: 0.33 }");
+
+    let queue = Queue {
+        len: (),
+        items: vec![7, 8, 9],
+    };
+    assert_eq!(format!("{:?}", queue), "Queue { len: 3, head: Some(7) }",);
+
+    let inventory = Inventory {
+        items: vec!["apple", "pear"],
+        capacity: 10,
+    };
+    assert_eq!(
+        format!("{:?}", inventory),
+        "Inventory { items: 2 of 10, capacity: 10 }",
+    );
+
+    let sample = Sample::Scaled {
+        value: -4,
+        scale: 3,
+    };
+    assert_eq!(format!("{:?}", sample), "Scaled { value: -4x3, scale: 3 }");
+
+    let measured = Measured {
+        value: OnlyDisplay,
+        unit: OnlyDebug,
+    };
+    assert_eq!(format!("{:?}", measured), "Measured { value: 42 (ms) }");
+}
@@ -0,0 +1,54 @@
+// Facts derived from a struct's fields can be shown alongside them without
+// storing them in a field of their own:
+//
+//     #[derive(CustomDebug)]
+//     #[debug(extra(name = "len", expr = "self.items.len()"))]
+//     pub struct Stack {
+//         items: Vec<u32>,
+//     }
+//
+// prints as `Stack { items: [1, 2], len: 2 }`. Each `extra` adds one entry
+// after the fields, in the order the attributes are written. The expression
+// is evaluated inside `fmt` and may use `self`.
+
+use derive_debug::CustomDebug;
+
+#[derive(CustomDebug)]
+#[debug(extra(name = "len", expr = "self.items.len()"))]
+pub struct Stack {
+    items: Vec<u32>,
+}
+
+#[derive(CustomDebug)]
+#[debug(extra(name = "checksum", expr = "self.checksum()"))]
+#[debug(extra(
+    name = "state",
+    expr = "if self.sealed { \"sealed\" } else { \"open\" }"
+))]
+pub struct Packet {
+    payload: [u8; 3],
+    #[debug(skip)]
+    sealed: bool,
+}
+
+impl Packet {
+    fn checksum(&self) -> u8 {
+        self.payload
+            .iter()
+            .fold(0, |sum, byte| sum.wrapping_add(*byte))
+    }
+}
+
+fn main() {
+    let stack = Stack { items: vec![1, 2] };
+    assert_eq!(format!("{:?}", stack), "Stack { items: [1, 2], len: 2 }");
+
+    let packet = Packet {
+        payload: [1, 2, 3],
+        sealed: true,
+    };
+    assert_eq!(
+        format!("{:?}", packet),
+        r#"Packet { payload: [1, 2, 3], checksum: 6, state: "sealed" }"#,
+    );
+}
@@ -0,0 +1,78 @@
+// Two attributes shape how much room a type takes up in pretty-printed output.
+//
+// #[debug(compact)] on a type prints it on one line even under `{:#?}`, which
+// keeps small leaf types like points or ids from spreading over several lines
+// each when the structure around them is pretty-printed.
+//
+// #[debug(flatten)] on a field prints the fields of a nested struct as if they
+// were fields of the outer one. The nested type must itself derive CustomDebug
+// with named fields.
+
+use derive_debug::CustomDebug;
+
+#[derive(CustomDebug)]
+#[debug(compact)]
+pub struct Point {
+    x: i32,
+    y: i32,
+}
+
+#[derive(CustomDebug)]
+pub struct Shape {
+    name: &'static str,
+    origin: Point,
+}
+
+#[derive(CustomDebug)]
+pub struct Meta {
+    id: u32,
+    #[debug(skip_if = "Option::is_none")]
+    trace: Option<u64>,
+}
+
+#[derive(CustomDebug)]
+pub struct Request {
+    method: &'static str,
+    #[debug(flatten)]
+    meta: Meta,
+}
+
+#[derive(CustomDebug)]
+pub enum Event {
+    Sent {
+        #[debug(flatten)]
+        meta: Meta,
+        bytes: usize,
+    },
+}
+
+fn main() {
+    let shape = Shape {
+        name: "unit",
+        origin: Point { x: 0, y: 1 },
+    };
+    assert_eq!(
+        format!("{:#?}", shape),
+        "Shape {\n    name: \"unit\",\n    origin: Point { x: 0, y: 1 },\n}",
+    );
+    assert_eq!(format!("{:#?}", shape.origin), "Point { x: 0, y: 1 }");
+
+    let request = Request {
+        method: "GET",
+        meta: Meta {
+            id: 7,
+            trace: Some(99),
+        },
+    };
+    assert_eq!(
+        format!("{:?}", request),
+        "Request { method: \"GET\", id: 7, trace: Some(99) }",
+    );
+
+    // A field left out of the nested struct still marks the outer one.
+    let event = Event::Sent {
+        meta: Meta { id: 8, trace: None },
+        bytes: 512,
+    };
+    assert_eq!(format!("{:?}", event), "Sent { id: 8, bytes: 512, .. }");
+}
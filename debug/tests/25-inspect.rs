@@ -0,0 +1,81 @@
+// derive(Inspect) hands the fields that CustomDebug would print to a
+// FieldVisitor, one at a time, so that the same field metadata can feed a
+// structured logger instead of a `fmt::Debug` string:
+//
+//     pub trait FieldVisitor {
+//         fn field(&mut self, name: &str, value: &dyn Debug);
+//     }
+//
+// The traits live in the derive_debug crate itself, which re-exports the
+// derives from the derive_debug-impl proc-macro crate alongside them.
+//
+// The usual attributes apply: skipped fields are not visited, renamed fields
+// are visited under their new name, formatted fields are visited as formatted,
+// and flattened fields visit the fields of the nested struct in place.
+
+use derive_debug::{CustomDebug, FieldVisitor, Inspect};
+use std::fmt::Debug;
+
+#[derive(CustomDebug, Inspect)]
+pub struct Request {
+    method: &'static str,
+    #[debug(rename = "url")]
+    path: String,
+    #[debug = "{}ms"]
+    elapsed: u64,
+    #[debug(skip)]
+    body: Vec<u8>,
+    #[debug(skip_if = "Option::is_none")]
+    user: Option<u32>,
+    #[debug(flatten)]
+    peer: Peer,
+}
+
+#[derive(CustomDebug, Inspect)]
+#[debug(extra(name = "v6", expr = "self.addr.contains(':')"))]
+pub struct Peer {
+    addr: &'static str,
+}
+
+#[derive(Inspect)]
+pub enum Status {
+    Ok(u16),
+    Failed { reason: &'static str },
+}
+
+/// Collects `key=value` pairs, as a logfmt logger would.
+#[derive(Default)]
+struct Logfmt(Vec<String>);
+
+impl FieldVisitor for Logfmt {
+    fn field(&mut self, name: &str, value: &dyn Debug) {
+        self.0.push(format!("{}={:?}", name, value));
+    }
+}
+
+fn logfmt(value: &dyn Inspect) -> String {
+    let mut visitor = Logfmt::default();
+    value.inspect(&mut visitor);
+    visitor.0.join(" ")
+}
+
+fn main() {
+    let request = Request {
+        method: "GET",
+        path: "/".to_owned(),
+        elapsed: 12,
+        body: Vec::new(),
+        user: None,
+        peer: Peer { addr: "::1" },
+    };
+    assert_eq!(
+        logfmt(&request),
+        r#"method="GET" url="/" elapsed=12ms addr="::1" v6=true"#,
+    );
+
+    assert_eq!(logfmt(&Status::Ok(200)), "0=200");
+    assert_eq!(
+        logfmt(&Status::Failed { reason: "timeout" }),
+        r#"reason="timeout""#,
+    );
+}
@@ -0,0 +1,117 @@
+// Does the expansion still work in a crate without the standard library, and
+// where the names it might be tempted to use unqualified mean something else?
+//
+// The generated code refers to everything through `::core`, including the
+// primitive types, so that it compiles in `#![no_std]` crates and next to a
+// crate's own `Result` alias, a `Debug` that is not the trait, or even a local
+// module that happens to be called `core`.
+//
+// The standard library is linked under another name only so that this test
+// can have a `main` and use `format!`; the derives below never see `std`.
+
+#![no_std]
+#![allow(non_camel_case_types)]
+
+extern crate std as real_std;
+
+use derive_debug::{CustomDebug, CustomDisplay, FieldVisitor, Inspect};
+use real_std::format;
+use real_std::string::String;
+
+type Debug = ();
+type Display = ();
+type Formatter = ();
+type Result = ();
+type Option = ();
+type Some = ();
+type None = ();
+type Ok = ();
+type Err = ();
+type Box = ();
+type Vec = ();
+type Iterator = ();
+type IntoIterator = ();
+type Sized = ();
+type bool = ();
+type usize = ();
+type str = ();
+type u8 = ();
+
+mod core {}
+mod std {}
+mod fmt {}
+
+fn is_zero(value: &u32) -> ::core::primitive::bool {
+    *value == 0
+}
+
+fn fmt_tag(tag: &char, f: &mut ::core::fmt::Formatter) -> ::core::fmt::Result {
+    ::core::write!(f, "#{}", tag)
+}
+
+#[derive(CustomDebug, CustomDisplay, Inspect)]
+#[debug(extra(name = "twice", expr = "self.id * 2"))]
+#[display("{id}: {name}")]
+pub struct Record<T> {
+    id: u32,
+    #[debug(max_len = 3)]
+    name: &'static ::core::primitive::str,
+    #[debug(max_items = 2)]
+    bytes: [::core::primitive::u8; 3],
+    #[debug = "{:#x}"]
+    flags: u32,
+    #[debug(with = "fmt_tag")]
+    tag: char,
+    #[debug(skip_if = "is_zero")]
+    count: u32,
+    #[debug(flatten)]
+    inner: Inner<T>,
+}
+
+#[derive(CustomDebug, Inspect)]
+#[debug(compact)]
+pub struct Inner<T> {
+    value: T,
+}
+
+#[derive(CustomDebug)]
+pub enum Either {
+    Left(u32),
+    Right { value: i32 },
+}
+
+struct Count(u32);
+
+impl FieldVisitor for Count {
+    fn field(&mut self, _name: &::core::primitive::str, _value: &dyn ::core::fmt::Debug) {
+        self.0 += 1;
+    }
+}
+
+fn main() {
+    let record = Record {
+        id: 1,
+        name: "record",
+        bytes: [1, 2, 3],
+        flags: 16,
+        tag: 'a',
+        count: 0,
+        inner: Inner { value: 'x' },
+    };
+    let debug: String = format!("{:?}", record);
+    assert_eq!(
+        debug,
+        r#"Record { id: 1, name: "rec"... (3 more), bytes: [1, 2, ... (1 more)], flags: 0x10, tag: #a, value: 'x', twice: 2, .. }"#,
+    );
+    assert_eq!(format!("{}", record), "1: record");
+
+    let mut count = Count(0);
+    record.inspect(&mut count);
+    assert_eq!(count.0, 7);
+
+    assert_eq!(format!("{:?}", Either::Left(1)), "Left(1)");
+    assert_eq!(
+        format!("{:?}", Either::Right { value: -1 }),
+        "Right { value: -1 }",
+    );
+}
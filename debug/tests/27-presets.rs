@@ -0,0 +1,111 @@
+// Shorthands for the formats that come up over and over with low-level data:
+//
+//     #[debug(hex)]      integers in hex, like `{:x?}`
+//     #[debug(binary)]   integers in binary
+//     #[debug(bytes)]    bytes as an escaped byte string, b"GET \x00"
+//     #[debug(hexdump)]  bytes as hex, with offsets and ASCII under {:#?}
+//
+// Hex and binary apply element by element to arrays, slices, Vecs, Boxes and
+// Options, nested to any depth, which a format string like
+// `#[debug = "0x{:08x}"]` cannot do. Any other field type is an error. As with
+// `{:x?}`, the `0x` and `0b` prefixes appear under `{:#?}`, where nested lists
+// are also spread over several lines. Bytes and hexdump apply to anything
+// that derefs to `[u8]`, and to Options of anything `AsRef<[u8]>`.
+
+use derive_debug::CustomDebug;
+
+#[derive(CustomDebug)]
+pub struct Registers {
+    #[debug(hex)]
+    status: u8,
+    #[debug(binary)]
+    mask: u16,
+    #[debug(hex)]
+    window: [u32; 2],
+    #[debug(hex)]
+    last: Option<i32>,
+    #[debug(hex)]
+    history: Vec<u64>,
+    #[debug(hex)]
+    pending: Option<Vec<u32>>,
+    #[debug(binary)]
+    planes: Vec<Vec<u8>>,
+}
+
+#[derive(CustomDebug)]
+pub struct Frame {
+    #[debug(bytes)]
+    header: [u8; 5],
+    #[debug(bytes)]
+    trailer: Option<Vec<u8>>,
+    #[debug(hexdump)]
+    payload: Vec<u8>,
+}
+
+fn main() {
+    let registers = Registers {
+        status: 0x2a,
+        mask: 0b101,
+        window: [0x10, 0xff],
+        last: Some(0x7f),
+        history: vec![],
+        pending: Some(vec![0xab]),
+        planes: vec![vec![1, 2], vec![]],
+    };
+    assert_eq!(
+        format!("{:?}", registers),
+        concat!(
+            "Registers { status: 2a, mask: 101, window: [10, ff], last: Some(7f), ",
+            "history: [], pending: Some([ab]), planes: [[1, 10], []] }",
+        ),
+    );
+    assert_eq!(
+        format!("{:#?}", registers),
+        "\
+Registers {
+    status: 0x2a,
+    mask: 0b101,
+    window: [
+        0x10,
+        0xff,
+    ],
+    last: Some(
+        0x7f,
+    ),
+    history: [],
+    pending: Some(
+        [
+            0xab,
+        ],
+    ),
+    planes: [
+        [
+            0b1,
+            0b10,
+        ],
+        [],
+    ],
+}",
+    );
+
+    let frame = Frame {
+        header: *b"GET \x00",
+        trailer: Some(b"\r\n".to_vec()),
+        payload: b"Hello, world! 0123456789".to_vec(),
+    };
+    assert_eq!(
+        format!("{:?}", frame),
+        r#"Frame { header: b"GET \x00", trailer: Some(b"\r\n"), payload: 48 65 6c 6c 6f 2c 20 77 6f 72 6c 64 21 20 30 31 32 33 34 35 36 37 38 39 }"#,
+    );
+    assert_eq!(
+        format!("{:#?}", frame),
+        r#"Frame {
+    header: b"GET \x00",
+    trailer: Some(
+        b"\r\n",
+    ),
+    payload: 00000000  48 65 6c 6c 6f 2c 20 77 6f 72 6c 64 21 20 30 31  |Hello, world! 01|
+    00000010  32 33 34 35 36 37 38 39                          |23456789|,
+}"#,
+    );
+}
@@ -0,0 +1,89 @@
+// FFI bindings are full of types the standard library's derive cannot handle.
+//
+// A union can't be printed without knowing which of its fields is active, so
+// by default it prints as `Name { .. }`. Two attributes print more, each on a
+// promise that the derive cannot check. Breaking the promise makes the safe
+// Debug impl read uninitialized memory, so, like an unsafe block, both are
+// spelled `unsafe_`:
+//
+// - #[debug(unsafe_union_field = "...")] prints that field, as if the union
+//   were a struct with just that field. It is read whatever field was last
+//   written, so it must always hold a valid value of its type.
+//
+// - #[debug(unsafe_raw_bytes)] prints the union's bytes in hex. All of them
+//   are read, so all of them must always be initialized, as when every field
+//   covers the union's whole size without padding, or the union is zeroed or
+//   filled in by C code.
+//
+// Fields of a #[repr(packed)] struct may be unaligned, so references to them
+// are not allowed. The fields are copied out before printing instead, which
+// requires them to be Copy, just as it does for the standard library's derive.
+
+use derive_debug::{CustomDebug, CustomDisplay};
+
+#[derive(CustomDebug, Clone, Copy)]
+pub union Opaque {
+    small: u8,
+    large: u32,
+}
+
+#[derive(CustomDebug, Clone, Copy)]
+#[repr(C)]
+#[debug(unsafe_raw_bytes)]
+pub union Value {
+    int: u32,
+    bytes: [u8; 4],
+}
+
+#[derive(CustomDebug, Clone, Copy)]
+#[repr(C)]
+#[debug(unsafe_union_field = "int")]
+pub union Register {
+    #[debug(hex)]
+    int: u32,
+    bytes: [u8; 4],
+}
+
+#[derive(CustomDebug, CustomDisplay, Clone, Copy)]
+#[repr(C, packed)]
+#[display("{tag}:{len}")]
+pub struct Header {
+    tag: u8,
+    #[debug(hex)]
+    len: u32,
+    #[debug(skip_if = "is_zero")]
+    flags: u16,
+}
+
+#[derive(CustomDebug)]
+#[repr(packed(2))]
+pub struct Pair(u8, #[debug = "{}!"] u64);
+
+fn is_zero(value: &u16) -> bool {
+    *value == 0
+}
+
+fn main() {
+    let opaque = Opaque { small: 1 };
+    assert_eq!(format!("{:?}", opaque), "Opaque { .. }");
+    assert_eq!(format!("{:?}", Opaque { large: 2 }), "Opaque { .. }");
+
+    let value = Value {
+        bytes: [1, 2, 3, 0xff],
+    };
+    assert_eq!(format!("{:?}", value), "Value([01, 02, 03, ff])");
+
+    let register = Register { int: 0x2a };
+    assert_eq!(format!("{:?}", register), "Register { int: 2a }");
+    assert_eq!(format!("{:#?}", register), "Register {\n    int: 0x2a,\n}");
+
+    let header = Header {
+        tag: 7,
+        len: 255,
+        flags: 0,
+    };
+    assert_eq!(format!("{:?}", header), "Header { tag: 7, len: ff, .. }");
+    assert_eq!(header.to_string(), "7:255");
+
+    assert_eq!(format!("{:?}", Pair(1, 2)), "Pair(1, 2!)");
+}
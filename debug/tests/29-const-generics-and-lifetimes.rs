@@ -0,0 +1,86 @@
+// Lifetimes and const generic parameters pass through to the generated impls
+// untouched. Only type parameters are given bounds, so the impl for `Buf`
+// below is
+//
+//     impl<'a, T, const N: usize> Debug for Buf<'a, T, N>
+//     where
+//         T: Debug,
+//
+// with nothing required of `'a` or `N`. A const parameter used as a generic
+// argument, as in `Ring<T, N>`, is not mistaken for a type either.
+
+use derive_debug::{CustomDebug, CustomDisplay, FieldVisitor, Inspect};
+use std::fmt::Debug;
+
+#[derive(CustomDebug)]
+pub struct Buf<'a, T, const N: usize> {
+    data: &'a [T; N],
+}
+
+#[derive(CustomDebug, Inspect)]
+pub struct Ring<T, const N: usize = 4> {
+    #[debug(max_items = 2)]
+    slots: [Option<T>; N],
+    head: usize,
+}
+
+#[derive(CustomDebug)]
+pub struct Window<'a, 'b: 'a, T: 'a, const N: usize>
+where
+    T: Clone,
+{
+    ring: &'a Ring<T, N>,
+    #[debug(skip)]
+    label: &'b str,
+}
+
+#[derive(CustomDebug, CustomDisplay)]
+pub enum Chunk<'a, const N: usize> {
+    #[display("{0:?}")]
+    Full(&'a [u8; N]),
+    #[display("{len} bytes")]
+    Partial { len: usize },
+}
+
+fn assert_debug<T: Debug>() {}
+
+struct Count(usize);
+
+impl FieldVisitor for Count {
+    fn field(&mut self, _name: &str, _value: &dyn Debug) {
+        self.0 += 1;
+    }
+}
+
+fn main() {
+    let data = [1, 2, 3];
+    let buf = Buf { data: &data };
+    assert_eq!(format!("{:?}", buf), "Buf { data: [1, 2, 3] }");
+
+    let ring: Ring<u8> = Ring {
+        slots: [Some(1), None, None, None],
+        head: 0,
+    };
+    assert_eq!(
+        format!("{:?}", ring),
+        "Ring { slots: [Some(1), None, ... (2 more)], head: 0 }",
+    );
+    let mut count = Count(0);
+    ring.inspect(&mut count);
+    assert_eq!(count.0, 2);
+
+    let window = Window {
+        ring: &ring,
+        label: "w",
+    };
+    assert_eq!(
+        format!("{:?}", window),
+        "Window { ring: Ring { slots: [Some(1), None, ... (2 more)], head: 0 } }",
+    );
+
+    assert_eq!(Chunk::Full(&[1, 2]).to_string(), "[1, 2]");
+
+    // Nothing is required of the lifetime or the const parameter.
+    assert_debug::<Buf<'static, u8, 0>>();
+    assert_debug::<Chunk<'static, 8>>();
+}
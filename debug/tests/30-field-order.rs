@@ -0,0 +1,83 @@
+// Fields are laid out in memory for the machine, but read by people, who look
+// for the identifying fields first. The printed order can be set separately
+// from the declaration order:
+//
+//     #[debug(order = N)] on a field prints it ahead of the fields without an
+//     order, lowest N first.
+//
+//     #[debug(sort_fields)] on the type prints the remaining fields in
+//     alphabetical order of their printed names.
+//
+// Ordering only applies to named fields; tuple fields are printed by position.
+
+use derive_debug::{CustomDebug, FieldVisitor, Inspect};
+use std::fmt::Debug;
+
+#[derive(CustomDebug, Inspect)]
+pub struct Connection {
+    buffer: [u8; 4],
+    state: u8,
+    #[debug(order = 1)]
+    name: &'static str,
+    #[debug(order = 0)]
+    id: u32,
+}
+
+#[derive(CustomDebug)]
+#[debug(sort_fields)]
+pub struct Sorted {
+    zeta: u8,
+    #[debug(rename = "beta")]
+    alpha: u8,
+    gamma: u8,
+    #[debug(order = -1)]
+    omega: u8,
+}
+
+#[derive(CustomDebug)]
+#[debug(sort_fields)]
+pub enum Message {
+    Move { y: i32, x: i32 },
+    Write(u8, u8),
+}
+
+struct Names(Vec<String>);
+
+impl FieldVisitor for Names {
+    fn field(&mut self, name: &str, _value: &dyn Debug) {
+        self.0.push(name.to_owned());
+    }
+}
+
+fn main() {
+    let connection = Connection {
+        buffer: [0; 4],
+        state: 1,
+        name: "db",
+        id: 7,
+    };
+    assert_eq!(
+        format!("{:?}", connection),
+        r#"Connection { id: 7, name: "db", buffer: [0, 0, 0, 0], state: 1 }"#,
+    );
+    let mut names = Names(Vec::new());
+    connection.inspect(&mut names);
+    assert_eq!(names.0, ["id", "name", "buffer", "state"]);
+
+    let sorted = Sorted {
+        zeta: 1,
+        alpha: 2,
+        gamma: 3,
+        omega: 4,
+    };
+    assert_eq!(
+        format!("{:?}", sorted),
+        "Sorted { omega: 4, beta: 2, gamma: 3, zeta: 1 }",
+    );
+
+    assert_eq!(
+        format!("{:?}", Message::Move { y: 2, x: 1 }),
+        "Move { x: 1, y: 2 }",
+    );
+    assert_eq!(format!("{:?}", Message::Write(1, 2)), "Write(1, 2)");
+}
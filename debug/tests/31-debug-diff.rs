@@ -0,0 +1,176 @@
+// derive(DebugDiff) compares two values field by field, for when assert_eq!
+// fails on a struct too large to spot the change in by eye:
+//
+//     pub trait DebugDiff {
+//         fn debug_diff(&self, other: &Self) -> Option<String>;
+//     }
+//
+// The result lists only the fields that differ, one per line as
+// `path: left -> right`, or is None if none do. A field whose type also
+// derives DebugDiff is compared field by field, with the nested paths joined
+// by dots; any other field is compared by its Debug output. Building the
+// result takes an allocator, so the derive and the trait belong to the crate's
+// `alloc` feature. It is on by default, and targets without an allocator can
+// turn it off and keep the other derives.
+//
+// Fields are compared the way CustomDebug prints them: skipped fields are not
+// compared, renamed fields are reported under their new name, and formatted
+// fields are compared as formatted, so a field printed with a coarser format
+// only counts as changed if its printed form does. Flattened fields report the
+// paths of the nested struct in place. Enums in different variants report the
+// variant names.
+//
+// Whether a field recurses is decided where the impl is compiled, so inside a
+// generic type a field of type T is compared by its Debug output, even if T
+// turns out to derive DebugDiff. Marking the field #[debug(diff)] compares it
+// with DebugDiff instead, and requires the field type, here T, to implement it.
+//
+// The trait can also be implemented by hand, for a type whose fields are
+// better compared some other way; a derived impl recurses into it like any
+// other.
+
+use derive_debug::{CustomDebug, DebugDiff};
+
+#[derive(CustomDebug, DebugDiff, Clone)]
+pub struct Config {
+    name: String,
+    #[debug(rename = "listen")]
+    addr: Address,
+    #[debug = "{:.1}"]
+    ratio: f64,
+    #[debug(skip)]
+    generation: u64,
+    #[debug(hex)]
+    flags: u8,
+    mode: Mode,
+    version: Version,
+    tags: Vec<&'static str>,
+    #[debug(flatten)]
+    limits: Limits,
+}
+
+#[derive(CustomDebug, DebugDiff, Clone)]
+pub struct Address {
+    host: &'static str,
+    port: u16,
+}
+
+#[derive(CustomDebug, DebugDiff, Clone)]
+#[debug(extra(name = "total", expr = "self.read + self.write"))]
+pub struct Limits {
+    read: u32,
+    write: u32,
+}
+
+#[derive(CustomDebug, DebugDiff, Clone)]
+pub enum Mode {
+    Off,
+    Fixed(u32),
+    Auto { min: u32, max: u32 },
+}
+
+#[derive(Debug, Clone)]
+pub struct Version(&'static str);
+
+impl DebugDiff for Version {
+    fn debug_diff(&self, other: &Self) -> Option<String> {
+        // A leading "v" is only spelling.
+        let left = self.0.trim_start_matches('v');
+        let right = other.0.trim_start_matches('v');
+        if left == right {
+            None
+        } else {
+            Some(format!("number: {} -> {}", left, right))
+        }
+    }
+}
+
+#[derive(CustomDebug, DebugDiff, Clone)]
+pub struct Pair<T>(T, T);
+
+#[derive(CustomDebug, DebugDiff, Clone)]
+pub struct Labeled<T> {
+    label: &'static str,
+    #[debug(diff)]
+    value: T,
+}
+
+fn main() {
+    let config = Config {
+        name: "primary".to_owned(),
+        addr: Address {
+            host: "localhost",
+            port: 8080,
+        },
+        ratio: 0.51,
+        generation: 1,
+        flags: 0x0f,
+        mode: Mode::Auto { min: 1, max: 4 },
+        version: Version("1.2"),
+        tags: vec!["a"],
+        limits: Limits { read: 10, write: 5 },
+    };
+    assert_eq!(config.debug_diff(&config.clone()), None);
+
+    let mut other = config.clone();
+    other.addr.port = 8081;
+    other.ratio = 0.53;
+    other.generation = 2;
+    other.flags = 0x1f;
+    other.mode = Mode::Auto { min: 1, max: 8 };
+    other.tags.push("b");
+    other.limits.write = 6;
+    assert_eq!(
+        config.debug_diff(&other).unwrap(),
+        "listen.port: 8080 -> 8081\n\
+         flags: f -> 1f\n\
+         mode.max: 4 -> 8\n\
+         tags: [\"a\"] -> [\"a\", \"b\"]\n\
+         write: 5 -> 6\n\
+         total: 15 -> 16",
+    );
+
+    other = config.clone();
+    other.name = "backup".to_owned();
+    other.mode = Mode::Fixed(3);
+    other.version = Version("v1.2");
+    assert_eq!(
+        config.debug_diff(&other).unwrap(),
+        "name: \"primary\" -> \"backup\"\n\
+         mode.variant: Auto -> Fixed",
+    );
+    other.version = Version("1.3");
+    assert_eq!(
+        config.debug_diff(&other).unwrap(),
+        "name: \"primary\" -> \"backup\"\n\
+         mode.variant: Auto -> Fixed\n\
+         version.number: 1.2 -> 1.3",
+    );
+    assert_eq!(
+        Mode::Fixed(1).debug_diff(&Mode::Fixed(2)).unwrap(),
+        "0: 1 -> 2",
+    );
+    assert_eq!(Mode::Off.debug_diff(&Mode::Off), None);
+
+    // Inside a generic impl, fields of a type parameter are compared by their
+    // Debug output, since T is not known to implement DebugDiff.
+    let pair = Pair(
+        Address { host: "a", port: 1 },
+        Address { host: "b", port: 2 },
+    );
+    let mut changed = pair.clone();
+    changed.1.port = 3;
+    assert_eq!(
+        pair.debug_diff(&changed).unwrap(),
+        "1: Address { host: \"b\", port: 2 } -> Address { host: \"b\", port: 3 }",
+    );
+
+    // Marked debug(diff), they are compared with DebugDiff instead.
+    let labeled = Labeled {
+        label: "origin",
+        value: Address { host: "a", port: 1 },
+    };
+    let mut changed = labeled.clone();
+    changed.value.port = 2;
+    assert_eq!(labeled.debug_diff(&changed).unwrap(), "value.port: 1 -> 2");
+}
@@ -0,0 +1,115 @@
+// Enums describing hardware registers, like the DeliveryMode in the bitfield
+// tests, are defined by their discriminants, so the variant name alone often
+// isn't what you want to see when going over a register dump.
+//
+// #[debug(discriminant)] prints each variant with its discriminant, as
+// `Fixed(0)`, and #[debug(discriminant = "hex")] as `Fixed = 0x00`, padded to
+// the width of the enum's integer representation if it has one. Variants
+// without an explicit discriminant count up from the previous one, which for
+// enums without any is simply the variant's index.
+//
+// Fieldless enums are supported with or without a #[repr]. Variants with
+// fields need an integer representation such as #[repr(u8)], which is where
+// their discriminant is read from. Their fields follow it in braces, as a
+// struct's would, with tuple fields named by their index: `Read(4) { 0: beef }`
+// or `Read = 0x04 { 0: beef }`.
+
+use derive_debug::CustomDebug;
+
+#[derive(CustomDebug)]
+#[debug(discriminant)]
+pub enum DeliveryMode {
+    Fixed = 0b000,
+    Lowest = 0b001,
+    SMI = 0b010,
+    RemoteRead = 0b011,
+    NMI = 0b100,
+    Init = 0b101,
+    Startup = 0b110,
+    External = 0b111,
+}
+
+#[derive(CustomDebug)]
+#[debug(discriminant)]
+pub enum Index {
+    First,
+    #[debug(name = "2nd")]
+    Second,
+    Tenth = 10,
+    Eleventh,
+}
+
+#[derive(CustomDebug)]
+#[repr(u8)]
+#[debug(discriminant = "hex")]
+pub enum Level {
+    Off,
+    Low = 0x10,
+    High = 0xf0,
+}
+
+#[derive(CustomDebug)]
+#[repr(i16)]
+#[debug(discriminant = "hex")]
+pub enum Signed {
+    Negative = -1,
+}
+
+#[derive(CustomDebug)]
+#[repr(u8)]
+#[debug(discriminant)]
+pub enum Command {
+    Nop,
+    Read(#[debug(hex)] u16) = 4,
+    Write { addr: u16, len: u8 },
+    Copy(u16, u16),
+}
+
+#[derive(CustomDebug)]
+#[repr(u8)]
+#[debug(discriminant = "hex")]
+pub enum Packet {
+    Ack,
+    Data(#[debug(hex)] u16) = 0x10,
+}
+
+fn main() {
+    assert_eq!(format!("{:?}", DeliveryMode::Fixed), "Fixed(0)");
+    assert_eq!(format!("{:?}", DeliveryMode::External), "External(7)");
+
+    assert_eq!(format!("{:?}", Index::First), "First(0)");
+    assert_eq!(format!("{:?}", Index::Second), "2nd(1)");
+    assert_eq!(format!("{:?}", Index::Eleventh), "Eleventh(11)");
+
+    assert_eq!(format!("{:?}", Level::Off), "Off = 0x00");
+    assert_eq!(format!("{:?}", Level::High), "High = 0xf0");
+    assert_eq!(format!("{:?}", Signed::Negative), "Negative = 0xffff");
+
+    assert_eq!(format!("{:?}", Command::Nop), "Nop(0)");
+    assert_eq!(
+        format!("{:?}", Command::Read(0xbeef)),
+        "Read(4) { 0: beef }"
+    );
+    assert_eq!(
+        format!("{:?}", Command::Copy(1, 2)),
+        "Copy(6) { 0: 1, 1: 2 }",
+    );
+    assert_eq!(
+        format!("{:?}", Command::Write { addr: 1, len: 2 }),
+        "Write(5) { addr: 1, len: 2 }",
+    );
+    assert_eq!(
+        format!("{:#?}", Command::Write { addr: 1, len: 2 }),
+        "Write(5) {\n    addr: 1,\n    len: 2,\n}",
+    );
+
+    assert_eq!(format!("{:?}", Packet::Ack), "Ack = 0x00");
+    assert_eq!(
+        format!("{:?}", Packet::Data(0xbeef)),
+        "Data = 0x10 { 0: beef }",
+    );
+    assert_eq!(
+        format!("{:#?}", Packet::Data(0xbeef)),
+        "Data = 0x10 {\n    0: 0xbeef,\n}",
+    );
+}
@@ -0,0 +1,15 @@
+// `debug(hex)` and `debug(binary)` only know how to print integers and the
+// containers listed in test 27. Any other field type is reported at the key,
+// in terms of the attribute rather than of the derive's internals.
+
+use derive_debug::CustomDebug;
+
+#[derive(CustomDebug)]
+pub struct Sample {
+    #[debug(hex)]
+    level: f32,
+    #[debug(binary)]
+    labels: Option<Vec<String>>,
+}
+
+fn main() {}
@@ -1,12 +1,37 @@
 #[test]
 fn tests() {
     let t = trybuild::TestCases::new();
-    //t.pass("tests/01-parse.rs");
-    //t.pass("tests/02-impl-debug.rs");
-    //t.pass("tests/03-custom-format.rs");
-    //t.pass("tests/04-type-parameter.rs");
-    //t.pass("tests/05-phantom-data.rs");
-    //t.pass("tests/06-bound-trouble.rs");
-    //t.pass("tests/07-associated-type.rs");
-    //t.pass("tests/08-escape-hatch.rs");
+    t.pass("tests/01-parse.rs");
+    t.pass("tests/02-impl-debug.rs");
+    t.pass("tests/03-custom-format.rs");
+    t.pass("tests/04-type-parameter.rs");
+    t.pass("tests/05-phantom-data.rs");
+    t.pass("tests/06-bound-trouble.rs");
+    t.pass("tests/07-associated-type.rs");
+    t.pass("tests/08-escape-hatch.rs");
+    t.pass("tests/09-enums.rs");
+    t.pass("tests/10-tuple-and-unit-structs.rs");
+    t.compile_fail("tests/11-transparent-needs-one-field.rs");
+    t.pass("tests/12-skip-and-redact.rs");
+    t.pass("tests/13-debug-with.rs");
+    t.pass("tests/14-field-bound.rs");
+    t.pass("tests/15-bound-inference.rs");
+    t.pass("tests/16-rename.rs");
+    t.pass("tests/17-skip-if.rs");
+    t.pass("tests/18-truncation.rs");
+    t.pass("tests/19-display.rs");
+    t.compile_fail("tests/20-display-unknown-field.rs");
+    t.compile_fail("tests/21-malformed-format.rs");
+    t.pass("tests/22-sibling-fields.rs");
+    t.pass("tests/23-extra-entries.rs");
+    t.pass("tests/24-compact-and-flatten.rs");
+    t.pass("tests/25-inspect.rs");
+    t.pass("tests/26-no-std-and-redefined-prelude.rs");
+    t.pass("tests/27-presets.rs");
+    t.pass("tests/28-unions-and-packed.rs");
+    t.pass("tests/29-const-generics-and-lifetimes.rs");
+    t.pass("tests/30-field-order.rs");
+    t.pass("tests/31-debug-diff.rs");
+    t.pass("tests/32-discriminants.rs");
+    t.compile_fail("tests/33-radix-unsupported-type.rs");
 }